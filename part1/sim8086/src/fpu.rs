@@ -0,0 +1,155 @@
+use crate::instruction::{Instruction, Operand};
+use crate::memory_operand::{MemoryOperand, MemorySize};
+use crate::register::Register;
+use anyhow::{bail, Result};
+
+/// Arithmetic operations selected by the REG field of D8/DA/DC/DE, in encoding order
+const ARITHMETIC: [&str; 8] = [
+    "fadd", "fmul", "fcom", "fcomp", "fsub", "fsubr", "fdiv", "fdivr",
+];
+
+/// Integer variants of `ARITHMETIC`, used with a memory operand on DA/DE
+const INTEGER_ARITHMETIC: [&str; 8] = [
+    "fiadd", "fimul", "ficom", "ficomp", "fisub", "fisubr", "fidiv", "fidivr",
+];
+
+/// Decode an ESC opcode (D8-DF) whose second byte addresses memory.
+///
+/// The low three bits of the opcode together with the REG field select the operation, which also
+/// determines how large the memory operand is.
+pub fn decode_memory_form(opcode: u8, modrm: u8, mut mem: MemoryOperand) -> Result<Instruction> {
+    use MemorySize::{Dword, Qword, Tword, Word};
+
+    let reg = (modrm >> 3) & 0b111;
+    let (mnemonic, size) = match (opcode & 0b111, reg) {
+        (0b000, _) => (ARITHMETIC[reg as usize], Some(Dword)),
+        (0b001, 0b000) => ("fld", Some(Dword)),
+        (0b001, 0b010) => ("fst", Some(Dword)),
+        (0b001, 0b011) => ("fstp", Some(Dword)),
+        (0b001, 0b100) => ("fldenv", None),
+        (0b001, 0b101) => ("fldcw", Some(Word)),
+        (0b001, 0b110) => ("fnstenv", None),
+        (0b001, 0b111) => ("fnstcw", Some(Word)),
+        (0b010, _) => (INTEGER_ARITHMETIC[reg as usize], Some(Dword)),
+        (0b011, 0b000) => ("fild", Some(Dword)),
+        (0b011, 0b010) => ("fist", Some(Dword)),
+        (0b011, 0b011) => ("fistp", Some(Dword)),
+        (0b011, 0b101) => ("fld", Some(Tword)),
+        (0b011, 0b111) => ("fstp", Some(Tword)),
+        (0b100, _) => (ARITHMETIC[reg as usize], Some(Qword)),
+        (0b101, 0b000) => ("fld", Some(Qword)),
+        (0b101, 0b010) => ("fst", Some(Qword)),
+        (0b101, 0b011) => ("fstp", Some(Qword)),
+        (0b101, 0b100) => ("frstor", None),
+        (0b101, 0b110) => ("fnsave", None),
+        (0b101, 0b111) => ("fnstsw", Some(Word)),
+        (0b110, _) => (INTEGER_ARITHMETIC[reg as usize], Some(Word)),
+        (0b111, 0b000) => ("fild", Some(Word)),
+        (0b111, 0b010) => ("fist", Some(Word)),
+        (0b111, 0b011) => ("fistp", Some(Word)),
+        (0b111, 0b100) => ("fbld", Some(Tword)),
+        (0b111, 0b101) => ("fild", Some(Qword)),
+        (0b111, 0b110) => ("fbstp", Some(Tword)),
+        (0b111, 0b111) => ("fistp", Some(Qword)),
+        _ => bail!("reserved coprocessor encoding {opcode:#x} {modrm:#x}"),
+    };
+
+    mem.size = size;
+    Ok(Instruction::Fpu {
+        mnemonic,
+        operands: [Some(Operand::Memory(mem)), None],
+    })
+}
+
+/// Decode an ESC opcode (D8-DF) whose second byte has MOD = 11, operating on the register stack.
+///
+/// The R/M field selects the stack register `st(i)`, or for D9/DB/DE/DF picks one of the
+/// operations that take no explicit operand.
+pub fn decode_register_form(opcode: u8, modrm: u8) -> Result<Instruction> {
+    let reg = (modrm >> 3) & 0b111;
+    let st0 = Operand::FpuRegister(0);
+    let sti = Operand::FpuRegister(modrm & 0b111);
+
+    let (mnemonic, operands) = match (opcode & 0b111, reg) {
+        // fcom and fcomp compare st0 against st(i)
+        (0b000, 0b010 | 0b011) => (ARITHMETIC[reg as usize], [Some(sti), None]),
+        (0b000, _) => (ARITHMETIC[reg as usize], [Some(st0), Some(sti)]),
+        (0b001, 0b000) => ("fld", [Some(sti), None]),
+        (0b001, 0b001) => ("fxch", [Some(sti), None]),
+        (0b011, 0b100) => match modrm {
+            0xe0 => ("fneni", [None, None]),
+            0xe1 => ("fndisi", [None, None]),
+            0xe2 => ("fnclex", [None, None]),
+            0xe3 => ("fninit", [None, None]),
+            _ => bail!("reserved coprocessor encoding {opcode:#x} {modrm:#x}"),
+        },
+        (0b001, _) => (constant_or_function(modrm)?, [None, None]),
+        (0b100, 0b010 | 0b011) => (ARITHMETIC[reg as usize], [Some(sti), None]),
+        // With st(i) as destination the Intel manual swaps the reversed subtract/divide forms
+        (0b100, _) => (reversed_destination(reg), [Some(sti), Some(st0)]),
+        (0b101, 0b000) => ("ffree", [Some(sti), None]),
+        (0b101, 0b010) => ("fst", [Some(sti), None]),
+        (0b101, 0b011) => ("fstp", [Some(sti), None]),
+        (0b110, 0b011) if modrm == 0xd9 => ("fcompp", [None, None]),
+        (0b110, 0b000) => ("faddp", [Some(sti), Some(st0)]),
+        (0b110, 0b001) => ("fmulp", [Some(sti), Some(st0)]),
+        (0b110, 0b100) => ("fsubrp", [Some(sti), Some(st0)]),
+        (0b110, 0b101) => ("fsubp", [Some(sti), Some(st0)]),
+        (0b110, 0b110) => ("fdivrp", [Some(sti), Some(st0)]),
+        (0b110, 0b111) => ("fdivp", [Some(sti), Some(st0)]),
+        (0b111, 0b100) if modrm == 0xe0 => {
+            ("fnstsw", [Some(Operand::Register(Register::Ax)), None])
+        }
+        _ => bail!("reserved coprocessor encoding {opcode:#x} {modrm:#x}"),
+    };
+
+    Ok(Instruction::Fpu { mnemonic, operands })
+}
+
+/// Operand-less D9 operations: constants, stack control and transcendental functions
+fn constant_or_function(modrm: u8) -> Result<&'static str> {
+    Ok(match modrm {
+        0xd0 => "fnop",
+        0xe0 => "fchs",
+        0xe1 => "fabs",
+        0xe4 => "ftst",
+        0xe5 => "fxam",
+        0xe8 => "fld1",
+        0xe9 => "fldl2t",
+        0xea => "fldl2e",
+        0xeb => "fldpi",
+        0xec => "fldlg2",
+        0xed => "fldln2",
+        0xee => "fldz",
+        0xf0 => "f2xm1",
+        0xf1 => "fyl2x",
+        0xf2 => "fptan",
+        0xf3 => "fpatan",
+        0xf4 => "fxtract",
+        0xf5 => "fprem1",
+        0xf6 => "fdecstp",
+        0xf7 => "fincstp",
+        0xf8 => "fprem",
+        0xf9 => "fyl2xp1",
+        0xfa => "fsqrt",
+        0xfb => "fsincos",
+        0xfc => "frndint",
+        0xfd => "fscale",
+        0xfe => "fsin",
+        0xff => "fcos",
+        _ => bail!("reserved coprocessor encoding 0xd9 {modrm:#x}"),
+    })
+}
+
+/// DC arithmetic with st(i) as destination
+fn reversed_destination(reg: u8) -> &'static str {
+    match reg {
+        0b000 => "fadd",
+        0b001 => "fmul",
+        0b100 => "fsubr",
+        0b101 => "fsub",
+        0b110 => "fdivr",
+        0b111 => "fdiv",
+        _ => unreachable!("fcom/fcomp are decoded separately"),
+    }
+}
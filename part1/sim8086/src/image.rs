@@ -0,0 +1,197 @@
+use anyhow::{bail, ensure, Context, Result};
+
+/// Highest address + 1 that can be reached through a 16-bit instruction pointer
+const SEGMENT_SIZE: usize = 0x1_0000;
+
+/// A contiguous run of bytes loaded at an address
+pub struct Region {
+    pub address: usize,
+    pub bytes: Vec<u8>,
+}
+
+/// Program bytes placed at their load addresses, for inputs that need random access or that
+/// specify where their bytes belong
+pub struct Image {
+    /// Non-overlapping regions, ordered by address
+    pub regions: Vec<Region>,
+
+    /// Address execution starts at, if the input specifies one
+    pub entry: Option<u16>,
+}
+
+impl Image {
+    /// A raw binary, loaded at address 0. It has to fit in a single 64 KiB segment.
+    pub fn from_binary(bytes: Vec<u8>) -> Result<Self> {
+        ensure!(
+            bytes.len() <= SEGMENT_SIZE,
+            "{} bytes do not fit in a 64 KiB segment",
+            bytes.len()
+        );
+
+        Ok(Self {
+            regions: vec![Region { address: 0, bytes }],
+            entry: None,
+        })
+    }
+
+    /// Bytes written as hex text, e.g. "89 d9 88 e5" or "89d988e5". Bytes may be separated by
+    /// whitespace or commas and prefixed with `0x`.
+    pub fn from_hex_text(text: &str) -> Result<Self> {
+        let mut bytes = Vec::new();
+
+        for token in text.split(|c: char| c.is_ascii_whitespace() || c == ',') {
+            let digits = token.strip_prefix("0x").unwrap_or(token);
+            ensure!(
+                digits.len() % 2 == 0 && digits.is_ascii(),
+                "invalid hex bytes '{token}'"
+            );
+
+            for i in (0..digits.len()).step_by(2) {
+                let byte = u8::from_str_radix(&digits[i..i + 2], 16)
+                    .with_context(|| format!("invalid hex bytes '{token}'"))?;
+                bytes.push(byte);
+            }
+        }
+
+        Self::from_binary(bytes)
+    }
+
+    /// An Intel HEX file. Data records are placed at their load address, extended segment and
+    /// linear address records move the base and a start segment or linear address record sets
+    /// the entry point.
+    /// Everything has to fit in a single 64 KiB segment.
+    pub fn from_intel_hex(text: &str) -> Result<Self> {
+        let mut regions: Vec<Region> = Vec::new();
+        let mut entry = None;
+        let mut base = 0;
+
+        for (i, line) in text.lines().enumerate().map(|(i, l)| (i + 1, l.trim())) {
+            if line.is_empty() {
+                continue;
+            }
+
+            let record = parse_record(line).with_context(|| format!("line {i}: '{line}'"))?;
+            match record.kind {
+                // Data
+                0x00 => {
+                    let address = base + record.address as usize;
+                    ensure!(
+                        address + record.data.len() <= SEGMENT_SIZE,
+                        "line {i}: data at {address:#x} does not fit in a 64 KiB segment"
+                    );
+
+                    regions.push(Region {
+                        address,
+                        bytes: record.data,
+                    });
+                }
+                // End of file
+                0x01 => break,
+                // Extended segment address, the base is the segment times 16
+                0x02 => {
+                    ensure!(
+                        record.data.len() == 2,
+                        "line {i}: expected a 2 byte segment"
+                    );
+                    base = u16::from_be_bytes([record.data[0], record.data[1]]) as usize * 16;
+                }
+                // Start segment address, CS:IP
+                0x03 => {
+                    ensure!(record.data.len() == 4, "line {i}: expected a 4 byte CS:IP");
+                    let cs = u16::from_be_bytes([record.data[0], record.data[1]]) as usize;
+                    let ip = u16::from_be_bytes([record.data[2], record.data[3]]) as usize;
+                    let start = cs * 16 + ip;
+                    ensure!(
+                        start < SEGMENT_SIZE,
+                        "line {i}: start address {start:#x} is outside the first 64 KiB"
+                    );
+                    entry = Some(start as u16);
+                }
+                // Extended linear address, the upper 16 bits of the base
+                0x04 => {
+                    ensure!(
+                        record.data.len() == 2,
+                        "line {i}: expected 2 upper address bytes"
+                    );
+                    base = (u16::from_be_bytes([record.data[0], record.data[1]]) as usize) << 16;
+                }
+                // Start linear address, EIP
+                0x05 => {
+                    ensure!(record.data.len() == 4, "line {i}: expected a 4 byte EIP");
+                    let start = u32::from_be_bytes(record.data[..4].try_into().unwrap()) as usize;
+                    ensure!(
+                        start < SEGMENT_SIZE,
+                        "line {i}: start address {start:#x} is outside the first 64 KiB"
+                    );
+                    entry = Some(start as u16);
+                }
+                kind => bail!("line {i}: unsupported record type {kind:#04x}"),
+            }
+        }
+
+        // Records do not have to be in order, join the ones that follow on directly
+        regions.sort_by_key(|region| region.address);
+        let mut joined: Vec<Region> = Vec::with_capacity(regions.len());
+        for region in regions {
+            match joined.last_mut() {
+                Some(last) if last.address + last.bytes.len() > region.address => {
+                    bail!("data at {:#x} overlaps earlier data", region.address)
+                }
+                Some(last) if last.address + last.bytes.len() == region.address => {
+                    last.bytes.extend(region.bytes)
+                }
+                _ => joined.push(region),
+            }
+        }
+
+        Ok(Self {
+            regions: joined,
+            entry,
+        })
+    }
+
+    /// Bytes from `address` up to the end of the region that contains it
+    pub fn bytes_at(&self, address: usize) -> Option<&[u8]> {
+        self.regions.iter().find_map(|region| {
+            let offset = address.checked_sub(region.address)?;
+            region.bytes.get(offset..).filter(|bytes| !bytes.is_empty())
+        })
+    }
+}
+
+/// A single `:LLAAAATT<data>CC` line of an Intel HEX file
+struct Record {
+    address: u16,
+    kind: u8,
+    data: Vec<u8>,
+}
+
+fn parse_record(line: &str) -> Result<Record> {
+    let digits = line
+        .strip_prefix(':')
+        .context("expected ':' at the start")?;
+    ensure!(
+        digits.len() % 2 == 0 && digits.is_ascii(),
+        "expected pairs of hex digits"
+    );
+
+    let bytes = (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16))
+        .collect::<Result<Vec<u8>, _>>()
+        .context("expected pairs of hex digits")?;
+
+    // length, 2 address bytes, type and checksum surround the data
+    ensure!(bytes.len() >= 5, "record is too short");
+    let len = bytes[0] as usize;
+    ensure!(bytes.len() == len + 5, "record length does not match");
+
+    let checksum = bytes.iter().fold(0u8, |sum, b| sum.wrapping_add(*b));
+    ensure!(checksum == 0, "checksum mismatch");
+
+    Ok(Record {
+        address: u16::from_be_bytes([bytes[1], bytes[2]]),
+        kind: bytes[3],
+        data: bytes[4..4 + len].to_vec(),
+    })
+}
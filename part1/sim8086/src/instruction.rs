@@ -1,6 +1,8 @@
 use crate::memory_operand::MemoryOperand;
 use crate::register::Register;
+use crate::syntax::Syntax;
 
+#[derive(Copy, Clone)]
 pub enum Operand {
     // A register operand
     Register(Register),
@@ -10,70 +12,20 @@ pub enum Operand {
 
     // An immediate operand
     Immediate(i16),
+
+    // A register of the 8087 register stack, relative to the top
+    FpuRegister(u8),
+
+    // A branch target within the segment, resolved from its displacement
+    CodeAddress(u16),
+
+    // A branch target in another segment
+    FarAddress { segment: u16, offset: u16 },
 }
 
 impl std::fmt::Display for Operand {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
-        match self {
-            Operand::Register(reg) => write!(f, "{reg}"),
-            // hex pretty print with "0x" prefix
-            Operand::Immediate(imm) => write!(f, "{imm:#x}"),
-
-            Operand::Memory(mem) => {
-                // If there is a direct memory address, write it and return
-                if let Some(address) = mem.address {
-                    if let Some(size) = mem.size {
-                        write!(f, "{size} ")?;
-                    }
-
-                    //if let Some(segment) = mem.segment {
-                    //   write!(f, "{segment}:")?;
-                    //}
-
-                    write!(f, "[{address:#x}]")?;
-                    return Ok(());
-                }
-
-                // Start the memory bracket
-                if let Some(size) = mem.size {
-                    write!(f, "{size} ")?;
-                }
-
-                // If a segment exists, insert it here
-                //if let Some(segment) = mem.segment {
-                //   write!(f, "{segment}:")?;
-                //}
-
-                // Open the memory bracket
-                write!(f, "[")?;
-
-                // If the operand has a register(s), write them
-                if let Some(reg1) = mem.registers[0] {
-                    write!(f, "{reg1}")?;
-
-                    if let Some(reg2) = mem.registers[1] {
-                        write!(f, " + {reg2}")?;
-                    }
-                }
-
-                // Write the displacement if it exists
-                if let Some(displacement) = mem.displacement {
-                    if displacement != 0 {
-                        // Add pretty spacing around the sign of the offset
-                        if displacement.is_negative() {
-                            write!(f, " - ")?;
-                        } else {
-                            write!(f, " + ")?;
-                        }
-
-                        // Regardless, print the absolute value of the offset
-                        write!(f, "{:#x}", displacement.abs())?;
-                    }
-                }
-
-                write!(f, "]")
-            }
-        }
+        write!(f, "{}", self.display(Syntax::default()))
     }
 }
 
@@ -95,16 +47,183 @@ pub struct Rm(pub u8);
 pub struct Mod(pub u8);
 
 pub enum Instruction {
-    Mov { src: Operand, dest: Operand },
+    Mov {
+        src: Operand,
+        dest: Operand,
+    },
+    In {
+        src: Operand,
+        dest: Operand,
+    },
+    Out {
+        src: Operand,
+        dest: Operand,
+    },
+    Xchg {
+        src: Operand,
+        dest: Operand,
+    },
+    Lea {
+        src: Operand,
+        dest: Operand,
+    },
+    Lds {
+        src: Operand,
+        dest: Operand,
+    },
+    Les {
+        src: Operand,
+        dest: Operand,
+    },
+    Aam {
+        base: u8,
+    },
+    Aad {
+        base: u8,
+    },
+    Daa,
+    Das,
+    Aaa,
+    Aas,
+    Cbw,
+    Cwd,
+    Lahf,
+    Sahf,
+    Clc,
+    Stc,
+    Cmc,
+    Cld,
+    Std,
+    Cli,
+    Sti,
+    Nop,
+    Wait,
+    // Conditional jumps and loops, these fall through when not taken
+    Jcc {
+        mnemonic: &'static str,
+        target: u16,
+    },
+    Jmp {
+        target: Operand,
+    },
+    Call {
+        target: Operand,
+    },
+    Ret {
+        pop: Option<u16>,
+    },
+    Retf {
+        pop: Option<u16>,
+    },
+    Int {
+        vector: u8,
+    },
+    Int3,
+    Into,
+    Iret,
+    // 8087 coprocessor instruction
+    Fpu {
+        mnemonic: &'static str,
+        operands: [Option<Operand>; 2],
+    },
 }
 
-impl std::fmt::Display for Instruction {
-    #[allow(clippy::too_many_lines)]
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+impl Instruction {
+    /// Name of the instruction as written in assembly
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Instruction::Mov { .. } => "mov",
+            Instruction::In { .. } => "in",
+            Instruction::Out { .. } => "out",
+            Instruction::Xchg { .. } => "xchg",
+            Instruction::Lea { .. } => "lea",
+            Instruction::Lds { .. } => "lds",
+            Instruction::Les { .. } => "les",
+            Instruction::Aam { .. } => "aam",
+            Instruction::Aad { .. } => "aad",
+            Instruction::Daa => "daa",
+            Instruction::Das => "das",
+            Instruction::Aaa => "aaa",
+            Instruction::Aas => "aas",
+            Instruction::Cbw => "cbw",
+            Instruction::Cwd => "cwd",
+            Instruction::Lahf => "lahf",
+            Instruction::Sahf => "sahf",
+            Instruction::Clc => "clc",
+            Instruction::Stc => "stc",
+            Instruction::Cmc => "cmc",
+            Instruction::Cld => "cld",
+            Instruction::Std => "std",
+            Instruction::Cli => "cli",
+            Instruction::Sti => "sti",
+            Instruction::Nop => "nop",
+            Instruction::Wait => "wait",
+            Instruction::Jcc { mnemonic, .. } => mnemonic,
+            Instruction::Jmp { .. } => "jmp",
+            Instruction::Call { .. } => "call",
+            Instruction::Ret { .. } => "ret",
+            Instruction::Retf { .. } => "retf",
+            Instruction::Int { .. } => "int",
+            Instruction::Int3 => "int3",
+            Instruction::Into => "into",
+            Instruction::Iret => "iret",
+            Instruction::Fpu { mnemonic, .. } => mnemonic,
+        }
+    }
+
+    /// Explicit operands in assembly order (destination first)
+    pub fn operands(&self) -> [Option<Operand>; 2] {
         match self {
-            Instruction::Mov { dest, src } => {
-                write!(f, "mov {dest}, {src}")
+            Instruction::Mov { dest, src }
+            | Instruction::In { dest, src }
+            | Instruction::Out { dest, src }
+            | Instruction::Xchg { dest, src }
+            | Instruction::Lea { dest, src }
+            | Instruction::Lds { dest, src }
+            | Instruction::Les { dest, src } => [Some(*dest), Some(*src)],
+            // The base is implied when it is 10, the only form documented by Intel
+            Instruction::Aam { base } | Instruction::Aad { base } if *base != 10 => {
+                [Some(Operand::Immediate(i16::from(*base))), None]
+            }
+            Instruction::Jcc { target, .. } => [Some(Operand::CodeAddress(*target)), None],
+            Instruction::Jmp { target } | Instruction::Call { target } => [Some(*target), None],
+            Instruction::Ret { pop: Some(pop) } | Instruction::Retf { pop: Some(pop) } => {
+                [Some(Operand::Immediate(*pop as i16)), None]
             }
+            Instruction::Int { vector } => [Some(Operand::Immediate(i16::from(*vector))), None],
+            Instruction::Fpu { operands, .. } => *operands,
+            _ => [None, None],
         }
     }
+
+    /// Target of a direct branch or call within the segment
+    pub fn branch_target(&self) -> Option<u16> {
+        match self {
+            Instruction::Jcc { target, .. }
+            | Instruction::Jmp {
+                target: Operand::CodeAddress(target),
+            }
+            | Instruction::Call {
+                target: Operand::CodeAddress(target),
+            } => Some(*target),
+            _ => None,
+        }
+    }
+
+    /// Whether execution can continue with the instruction directly after this one
+    pub fn falls_through(&self) -> bool {
+        !matches!(
+            self,
+            Instruction::Jmp { .. }
+                | Instruction::Ret { .. }
+                | Instruction::Retf { .. }
+                | Instruction::Iret
+        )
+    }
+}
+
+impl std::fmt::Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "{}", self.display(Syntax::default()))
+    }
 }
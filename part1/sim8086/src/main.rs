@@ -1,133 +1,517 @@
-use anyhow::Result;
-use memory_operand::MemoryOperand;
-use std::io::{self, BufReader, Read, Write};
-use std::slice::Iter;
+use anyhow::{bail, Result};
+use memory_operand::{MemoryOperand, MemorySize};
+use std::io::{self, BufReader, BufWriter, Bytes, Read, Write};
+use std::iter::{Copied, Map};
+use std::slice;
 
+mod fpu;
+mod image;
 mod instruction;
 mod memory_operand;
 mod register;
+mod stats;
+mod syntax;
+mod traversal;
 
 use instruction::Instruction;
 
+use crate::image::Image;
 use crate::instruction::{Mod, Operand, Reg, Rm, Wide};
 use crate::register::Register;
+use crate::stats::Stats;
+use crate::syntax::Syntax;
 
 fn main() -> Result<()> {
-    // the binary takes a filepath
-    let path = std::env::args().nth(1).expect("no path given");
+    // the binary takes a filepath, or "-" to read from stdin
+    let mut stats = false;
+    let mut recursive = false;
+    let mut intel_hex = false;
+    let mut hex = None;
+    let mut syntax = Syntax::default();
+    let mut entries = Vec::new();
+    let mut path = None;
 
-    let f = std::fs::File::open(path)?;
-    let mut reader = BufReader::new(f);
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            // print a report about the instructions instead of the listing
+            "--stats" => stats = true,
+            // only decode what is reachable from the entry points, the rest is data
+            "--recursive" => recursive = true,
+            // an additional entry point for --recursive, e.g. a routine only reached indirectly
+            "--entry" => {
+                let entry = args.next().expect("no address given for --entry");
+                entries.push(parse_address(&entry)?);
+            }
+            "--syntax" => syntax = args.next().expect("no syntax given").parse()?,
+            // the input is an Intel HEX file rather than a raw binary
+            "--ihex" => intel_hex = true,
+            // the bytes are given as hex text instead of a path, "-" reads the text from stdin
+            "--hex" => hex = Some(args.next().expect("no bytes given for --hex")),
+            _ => path = Some(arg),
+        }
+    }
 
-    let mut buffer: Vec<u8> = Vec::new();
-    reader.read_to_end(&mut buffer)?;
+    if !entries.is_empty() && (!recursive || stats) {
+        bail!("--entry only applies to the --recursive listing");
+    }
 
-    println!("{:?}", buffer);
+    let input = if let Some(text) = hex {
+        let text = if text == "-" {
+            io::read_to_string(io::stdin())?
+        } else {
+            text
+        };
+        Input::Image(Image::from_hex_text(&text)?)
+    } else {
+        let path = path.expect("no path given");
+        let mut reader: Box<dyn Read> = if path == "-" {
+            Box::new(io::stdin().lock())
+        } else {
+            Box::new(std::fs::File::open(path)?)
+        };
 
-    let asm = decode(&buffer)?;
-    io::stdout().write_all(asm.as_bytes())?;
+        if intel_hex {
+            Input::Image(Image::from_intel_hex(&io::read_to_string(reader)?)?)
+        } else if recursive {
+            // following branches needs random access to the whole image
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes)?;
+            Input::Image(Image::from_binary(bytes)?)
+        } else {
+            Input::Stream(reader)
+        }
+    };
 
-    Ok(())
+    let result = match input {
+        _ if stats => report_stats(input, io::stdout().lock()),
+        Input::Image(image) if recursive => {
+            // execution starts where the input says, or else at its first byte. Entry points given
+            // on the command line are followed in addition to it.
+            let first = image.regions.first().map_or(0, |region| region.address);
+            entries.insert(0, image.entry.unwrap_or(first as u16));
+            traversal::write_listing(&image, &entries, syntax, io::stdout().lock())
+        }
+        _ => decode(input, syntax, io::stdout().lock()),
+    };
+
+    match result {
+        // The reader of a pipe (e.g. `head`) went away, there is no one left to write to
+        Err(e) if is_broken_pipe(&e) => Ok(()),
+        result => result,
+    }
 }
 
-fn decode(bytes: &[u8]) -> Result<String> {
-    let mut asm = String::from("bits 16\n\n");
-    let mut iter = bytes.iter();
+/// Where the bytes to decode come from
+enum Input {
+    /// A raw binary, decoded while it is being read
+    Stream(Box<dyn Read>),
+    /// Bytes loaded in memory at their load addresses
+    Image(Image),
+}
 
-    while let Some(b1) = iter.next() {
-        println!("{:08b}", b1);
+/// Parse an address given on the command line, either decimal or hexadecimal with `0x`
+fn parse_address(s: &str) -> Result<u16> {
+    Ok(match s.strip_prefix("0x") {
+        Some(hex) => u16::from_str_radix(hex, 16)?,
+        None => s.parse()?,
+    })
+}
 
-        // the first x amount of bits define the opcode and variant.
-        let inst = match *b1 {
-            // from-to
-             0b1000_1000..=0b1000_1011   // mov
+fn is_broken_pipe(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<io::Error>()
+        .is_some_and(|e| e.kind() == io::ErrorKind::BrokenPipe)
+}
 
-            => {
-                // 0 = instruction operates on byte data
-                // 1 = instruction operates on word data
-                let w = *b1 & 0b0000_0001;
-                let d = (*b1 & 0b0000_0010) >> 1 == 1;
+/// Decode the instructions of `input` and write the assembly to `writer` as it goes. Streamed
+/// input only keeps a small read buffer, so memory use does not grow with the input size.
+fn decode<W: Write>(input: Input, syntax: Syntax, writer: W) -> Result<()> {
+    let mut writer = BufWriter::new(writer);
 
-                let b2 = iter.next().expect("expected second byte");
-                let (mut reg1, mut reg2) = parse_mod_reg_rm_instr(&mut iter, *b2, Wide(w))?;
+    writeln!(writer, "{}\n", syntax.header())?;
 
-                // Direction field
-                // 0 = Instruction source is specified in REG field
-                // 1 = Instruction destination is specified in REG field
-                if !d {
-                    std::mem::swap(&mut reg1, &mut reg2);
+    match input {
+        Input::Stream(reader) => {
+            write_instructions(&mut ByteStream::new(reader), syntax, &mut writer)?;
+        }
+        Input::Image(image) => {
+            let origin = image.regions.first().map_or(0, |region| region.address);
+            for region in &image.regions {
+                if let Some(directive) = syntax.place_at(origin, region.address) {
+                    writeln!(writer, "{directive}")?;
                 }
-                Instruction::Mov { dest: reg1, src: reg2}
-            },
 
-            // Immediate to register
-            0b1011_0000..=0b10111111 => {
-                let reg = *b1 & 0b0000_0111;
-                let w = (*b1 & 0b0000_1000) >> 3;
-                let imm = if w == 1 {
-                    i16::from_le_bytes([*iter.next().expect("immediate byte"), 0])
-                } else {
-                    i16::from_le_bytes([*iter.next().expect("lower immediate byte"), *iter.next().expect("higher immediate byte")])
-                };
-                Instruction::Mov { dest: Operand::Register(Register::from_reg_w(Reg(reg), Wide(w))), src: Operand::Immediate(imm) }
+                let mut iter = ByteStream::from_slice(&region.bytes, region.address);
+                write_instructions(&mut iter, syntax, &mut writer)?;
             }
+        }
+    }
 
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_instructions<I: Iterator<Item = io::Result<u8>>, W: Write>(
+    iter: &mut ByteStream<I>,
+    syntax: Syntax,
+    writer: &mut W,
+) -> Result<()> {
+    while let Some(inst) = decode_instruction(iter)? {
+        writeln!(writer, "{}", inst.display(syntax))?;
+    }
+    Ok(())
+}
 
-            // Immediate to register/memory
-            0b1100_0110 | 0b1100_0111 => {
+/// Decode all instructions of `input` and write a report of the static instruction statistics
+fn report_stats<W: Write>(input: Input, mut writer: W) -> Result<()> {
+    let mut stats = Stats::default();
 
-                let b2 = *iter.next().expect("second byte");
-                let w = *b1 & 1;
+    match input {
+        Input::Stream(reader) => record_stats(&mut ByteStream::new(reader), &mut stats)?,
+        Input::Image(image) => {
+            for region in &image.regions {
+                let mut iter = ByteStream::from_slice(&region.bytes, region.address);
+                record_stats(&mut iter, &mut stats)?;
+            }
+        }
+    }
 
-                let mod_ = Mod(b2 >> 6);
-                let rm = Rm(b2 & 0b111);
+    write!(writer, "{stats}")?;
+    Ok(())
+}
 
-                match mod_.0 {
-                    0b00 => { todo!()},
-                    0b01 => { todo!()},
-                    0b10 => { todo!()},
-                    0b11 => { todo!()},
-                    _ => unsafe { std::hint::unreachable_unchecked() },
+fn record_stats<I: Iterator<Item = io::Result<u8>>>(
+    iter: &mut ByteStream<I>,
+    stats: &mut Stats,
+) -> Result<()> {
+    loop {
+        let start = iter.position;
+        match decode_instruction(iter) {
+            Ok(Some(inst)) => stats.record(&inst, iter.position - start),
+            Ok(None) => return Ok(()),
+            Err(e) => match e.downcast_ref::<io::Error>() {
+                // The last instruction is cut off, count it like any other one that does not decode
+                Some(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                    stats.record_unknown();
+                    return Ok(());
                 }
-                
+                // Reading the input failed, the rest of it is unknown
+                Some(_) => return Err(e),
+                // The decoder does not know every opcode, skip the bytes it read and carry on
+                None => stats.record_unknown(),
             },
+        }
+    }
+}
 
-            // Mov memory to accumulator
-            0b10100000 | 0b10100001 => {
-                let w= *b1 & 1;
-                let imm = if w == 1 {
-                    u16::from_le_bytes([*iter.next().expect("address low"), 0])
-                } else {
-                    u16::from_le_bytes([*iter.next().expect("address low"), *iter.next().expect("address high")])
-                };
-                Instruction::Mov { dest: Operand::Register(Register::Ax), src: Operand::Memory(MemoryOperand::direct_address(imm, Wide(w)))}
-            },
+/// Bytes of the input stream, keeping track of how many have been consumed
+struct ByteStream<I: Iterator<Item = io::Result<u8>>> {
+    bytes: I,
+    position: usize,
+}
 
-            // Mov accumulator to memory
-            0b10100010 | 0b10100011 => {
-                let w = *b1 & 1;
-                let imm = if w == 1 {
-                    u16::from_le_bytes([*iter.next().expect("address low"), 0])
-                } else {
-                    u16::from_le_bytes([*iter.next().expect("address low"), *iter.next().expect("address high")])
-                };
-                Instruction::Mov { dest: Operand::Memory(MemoryOperand::direct_address(imm, Wide(w))), src: Operand::Register(Register::Ax)}
-            },
+impl<R: Read> ByteStream<Bytes<BufReader<R>>> {
+    fn new(reader: R) -> Self {
+        Self::at(reader, 0)
+    }
 
-            // Register/memory to segment register
-            0b10001110 =>{ todo!()},
-            // Segment register to register/memory
-            0b10001100 =>{ todo!()},
-            _ => panic!("unimplemented opcode"),
-        };
-        asm.push_str(&format!("{}\n", inst));
+    /// Stream whose first byte is located at `position`
+    fn at(reader: R, position: usize) -> Self {
+        Self {
+            bytes: BufReader::new(reader).bytes(),
+            position,
+        }
+    }
+}
+
+/// Bytes of an in-memory slice, which cannot fail to read
+type SliceBytes<'a> = Map<Copied<slice::Iter<'a, u8>>, fn(u8) -> io::Result<u8>>;
+
+impl<'a> ByteStream<SliceBytes<'a>> {
+    /// Stream over `bytes`, whose first byte is located at `position`. Unlike [`ByteStream::at`]
+    /// it does not copy the bytes into a read buffer first.
+    fn from_slice(bytes: &'a [u8], position: usize) -> Self {
+        Self {
+            bytes: bytes.iter().copied().map(Ok),
+            position,
+        }
     }
+}
+
+impl<I: Iterator<Item = io::Result<u8>>> ByteStream<I> {
+    /// The next byte of an instruction that has already started, running out of input here
+    /// fails with `io::ErrorKind::UnexpectedEof`
+    fn next_byte(&mut self) -> io::Result<u8> {
+        self.next().unwrap_or_else(|| {
+            Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "input ends in the middle of an instruction",
+            ))
+        })
+    }
+}
+
+impl<I: Iterator<Item = io::Result<u8>>> Iterator for ByteStream<I> {
+    type Item = io::Result<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let byte = self.bytes.next();
+        if let Some(Ok(_)) = byte {
+            self.position += 1;
+        }
+        byte
+    }
+}
+
+/// Decode the next instruction, returns `None` once the input is exhausted
+fn decode_instruction<I: Iterator<Item = io::Result<u8>>>(
+    iter: &mut ByteStream<I>,
+) -> Result<Option<Instruction>> {
+    let Some(b1) = iter.next() else {
+        return Ok(None);
+    };
+    let b1 = b1?;
+
+    // the first x amount of bits define the opcode and variant.
+    let inst = match b1 {
+        // from-to
+         0b1000_1000..=0b1000_1011   // mov
+
+        => {
+            // 0 = instruction operates on byte data
+            // 1 = instruction operates on word data
+            let w = b1 & 0b0000_0001;
+            let d = (b1 & 0b0000_0010) >> 1 == 1;
+
+            let b2 = iter.next_byte()?;
+            let (mut reg1, mut reg2) = parse_mod_reg_rm_instr(iter, b2, Wide(w))?;
+
+            // Direction field
+            // 0 = Instruction source is specified in REG field
+            // 1 = Instruction destination is specified in REG field
+            if !d {
+                std::mem::swap(&mut reg1, &mut reg2);
+            }
+            Instruction::Mov { dest: reg1, src: reg2}
+        },
+
+        // Immediate to register
+        0b1011_0000..=0b10111111 => {
+            let reg = b1 & 0b0000_0111;
+            let w = (b1 & 0b0000_1000) >> 3;
+            let imm = if w == 1 {
+                i16::from_le_bytes([iter.next_byte()?, iter.next_byte()?])
+            } else {
+                i16::from_le_bytes([iter.next_byte()?, 0])
+            };
+            Instruction::Mov { dest: Operand::Register(Register::from_reg_w(Reg(reg), Wide(w))), src: Operand::Immediate(imm) }
+        }
+
+
+        // Immediate to register/memory
+        0b1100_0110 | 0b1100_0111 => {
+            let b2 = iter.next_byte()?;
+            bail!("unimplemented immediate to register/memory mov {b1:#x} {b2:#x}")
+        },
+
+        // Mov memory to accumulator
+        0b10100000 | 0b10100001 => {
+            let w = b1 & 1;
+            // the address is a full word either way, w only selects al or ax
+            let address = u16::from_le_bytes([iter.next_byte()?, iter.next_byte()?]);
+            Instruction::Mov { dest: Operand::Register(Register::from_reg_w(Reg(0), Wide(w))), src: Operand::Memory(MemoryOperand::direct_address(address, Wide(w)))}
+        },
+
+        // Mov accumulator to memory
+        0b10100010 | 0b10100011 => {
+            let w = b1 & 1;
+            let address = u16::from_le_bytes([iter.next_byte()?, iter.next_byte()?]);
+            Instruction::Mov { dest: Operand::Memory(MemoryOperand::direct_address(address, Wide(w))), src: Operand::Register(Register::from_reg_w(Reg(0), Wide(w)))}
+        },
+
+        // Register/memory to segment register
+        0b10001110 => bail!("unimplemented segment register mov {b1:#x}"),
+        // Segment register to register/memory
+        0b10001100 => bail!("unimplemented segment register mov {b1:#x}"),
+
+        // In from fixed port
+        0b1110_0100 | 0b1110_0101 => {
+            let w = b1 & 1;
+            let port = iter.next_byte()?;
+            Instruction::In { dest: Operand::Register(Register::from_reg_w(Reg(0), Wide(w))), src: Operand::Immediate(i16::from(port)) }
+        },
+
+        // In from variable port (DX)
+        0b1110_1100 | 0b1110_1101 => {
+            let w = b1 & 1;
+            Instruction::In { dest: Operand::Register(Register::from_reg_w(Reg(0), Wide(w))), src: Operand::Register(Register::Dx) }
+        },
+
+        // Out to fixed port
+        0b1110_0110 | 0b1110_0111 => {
+            let w = b1 & 1;
+            let port = iter.next_byte()?;
+            Instruction::Out { dest: Operand::Immediate(i16::from(port)), src: Operand::Register(Register::from_reg_w(Reg(0), Wide(w))) }
+        },
+
+        // Out to variable port (DX)
+        0b1110_1110 | 0b1110_1111 => {
+            let w = b1 & 1;
+            Instruction::Out { dest: Operand::Register(Register::Dx), src: Operand::Register(Register::from_reg_w(Reg(0), Wide(w))) }
+        },
+        // Exchange register/memory with register
+        0b1000_0110 | 0b1000_0111 => {
+            let w = b1 & 1;
+            let b2 = iter.next_byte()?;
+            let (reg, rm) = parse_mod_reg_rm_instr(iter, b2, Wide(w))?;
+            Instruction::Xchg { dest: reg, src: rm }
+        },
+
+        // Exchange register with accumulator (0x90 encodes xchg ax, ax)
+        0b1001_0000 => Instruction::Nop,
+        0b1001_0001..=0b1001_0111 => {
+            let reg = b1 & 0b0000_0111;
+            Instruction::Xchg { dest: Operand::Register(Register::Ax), src: Operand::Register(Register::from_reg_w(Reg(reg), Wide(1))) }
+        },
+
+        // Load effective address / pointer (always word sized)
+        0b1000_1101 | 0b1100_0101 | 0b1100_0100 => {
+            let b2 = iter.next_byte()?;
+            let (reg, mut mem) = parse_mod_reg_rm_instr(iter, b2, Wide(1))?;
+
+            // The operand is an address, not a value of a given size. A register has no address.
+            match &mut mem {
+                Operand::Memory(mem) => mem.size = None,
+                _ => bail!("invalid register operand for opcode {b1:#x}"),
+            }
+
+            match b1 {
+                0b1000_1101 => Instruction::Lea { dest: reg, src: mem },
+                0b1100_0101 => Instruction::Lds { dest: reg, src: mem },
+                _ => Instruction::Les { dest: reg, src: mem },
+            }
+        },
+
+        // Decimal and ASCII adjust
+        0b0010_0111 => Instruction::Daa,
+        0b0010_1111 => Instruction::Das,
+        0b0011_0111 => Instruction::Aaa,
+        0b0011_1111 => Instruction::Aas,
+
+        // ASCII adjust for multiply/divide, the second byte is the base (0x0a)
+        0b1101_0100 => Instruction::Aam { base: iter.next_byte()? },
+        0b1101_0101 => Instruction::Aad { base: iter.next_byte()? },
+
+        // Sign extension
+        0b1001_1000 => Instruction::Cbw,
+        0b1001_1001 => Instruction::Cwd,
+
+        // Flags to/from AH
+        0b1001_1111 => Instruction::Lahf,
+        0b1001_1110 => Instruction::Sahf,
+
+        // Processor control (flag bits)
+        0b1111_1000 => Instruction::Clc,
+        0b1111_1001 => Instruction::Stc,
+        0b1111_0101 => Instruction::Cmc,
+        0b1111_1100 => Instruction::Cld,
+        0b1111_1101 => Instruction::Std,
+        0b1111_1010 => Instruction::Cli,
+        0b1111_1011 => Instruction::Sti,
+
+        // Escape to the 8087 coprocessor
+        0b1101_1000..=0b1101_1111 => {
+            let b2 = iter.next_byte()?;
+            if b2 >> 6 == 0b11 {
+                fpu::decode_register_form(b1, b2)?
+            } else {
+                // the REG field is part of the opcode, only the memory operand is of interest
+                let (_, mem) = parse_mod_reg_rm_instr(iter, b2, Wide(1))?;
+                let Operand::Memory(mem) = mem else { unreachable!("MOD is not 11") };
+                fpu::decode_memory_form(b1, b2, mem)?
+            }
+        },
+
+        // Wait for the coprocessor
+        0b1001_1011 => Instruction::Wait,
+
+        // Conditional jumps, relative to the next instruction
+        0b0111_0000..=0b0111_1111 => {
+            let displacement = iter.next_byte()? as i8;
+            let target = relative_target(iter, i16::from(displacement));
+            Instruction::Jcc { mnemonic: CONDITIONAL_JUMPS[(b1 & 0b1111) as usize], target }
+        },
+
+        // Loops and jcxz, relative to the next instruction
+        0b1110_0000..=0b1110_0011 => {
+            let displacement = iter.next_byte()? as i8;
+            let target = relative_target(iter, i16::from(displacement));
+            Instruction::Jcc { mnemonic: LOOPS[(b1 & 0b11) as usize], target }
+        },
+
+        // Direct call/jump within segment
+        0b1110_1000 | 0b1110_1001 => {
+            let displacement = i16::from_le_bytes([iter.next_byte()?, iter.next_byte()?]);
+            let target = Operand::CodeAddress(relative_target(iter, displacement));
+            if b1 == 0b1110_1000 { Instruction::Call { target } } else { Instruction::Jmp { target } }
+        },
+
+        // Direct jump within segment, short
+        0b1110_1011 => {
+            let displacement = iter.next_byte()? as i8;
+            Instruction::Jmp { target: Operand::CodeAddress(relative_target(iter, i16::from(displacement))) }
+        },
+
+        // Direct call/jump intersegment
+        0b1001_1010 | 0b1110_1010 => {
+            let offset = u16::from_le_bytes([iter.next_byte()?, iter.next_byte()?]);
+            let segment = u16::from_le_bytes([iter.next_byte()?, iter.next_byte()?]);
+            let target = Operand::FarAddress { segment, offset };
+            if b1 == 0b1001_1010 { Instruction::Call { target } } else { Instruction::Jmp { target } }
+        },
+
+        // Return within segment / intersegment, optionally adding an immediate to SP
+        0b1100_0011 => Instruction::Ret { pop: None },
+        0b1100_0010 => Instruction::Ret { pop: Some(u16::from_le_bytes([iter.next_byte()?, iter.next_byte()?])) },
+        0b1100_1011 => Instruction::Retf { pop: None },
+        0b1100_1010 => Instruction::Retf { pop: Some(u16::from_le_bytes([iter.next_byte()?, iter.next_byte()?])) },
+
+        // Interrupts
+        0b1100_1100 => Instruction::Int3,
+        0b1100_1101 => Instruction::Int { vector: iter.next_byte()? },
+        0b1100_1110 => Instruction::Into,
+        0b1100_1111 => Instruction::Iret,
+
+        _ => bail!("unimplemented opcode {b1:#x}"),
+    };
+
+    Ok(Some(inst))
+}
+
+/// Conditional jumps in the order of their condition code (low nibble of the opcode)
+const CONDITIONAL_JUMPS: [&str; 16] = [
+    "jo", "jno", "jb", "jae", "je", "jne", "jbe", "ja", "js", "jns", "jp", "jnp", "jl", "jge",
+    "jle", "jg",
+];
+
+/// Loop instructions and jcxz in opcode order (E0-E3)
+const LOOPS: [&str; 4] = ["loopnz", "loopz", "loop", "jcxz"];
 
-    Ok(asm)
+/// Address of a branch target, `displacement` is relative to the instruction following it.
+/// Addresses wrap around like IP does.
+fn relative_target<I: Iterator<Item = io::Result<u8>>>(
+    iter: &ByteStream<I>,
+    displacement: i16,
+) -> u16 {
+    (iter.position as u16).wrapping_add(displacement as u16)
 }
 
 /// Parse byte with "mod|reg|r/m" bit pattern
-fn parse_mod_reg_rm_instr(iter: &mut Iter<u8>, b: u8, w: Wide) -> Result<(Operand, Operand)> {
+fn parse_mod_reg_rm_instr<I: Iterator<Item = io::Result<u8>>>(
+    iter: &mut ByteStream<I>,
+    b: u8,
+    w: Wide,
+) -> Result<(Operand, Operand)> {
     let rm = Rm(b & 0b111);
     let reg = Reg(b >> 3 & 0b111);
     // indicates whether one of the operands is in memory or whether both operands are registers
@@ -139,10 +523,7 @@ fn parse_mod_reg_rm_instr(iter: &mut Iter<u8>, b: u8, w: Wide) -> Result<(Operan
         0b00 => {
             // exception: when R/M = 110, 16 bit displacement follows
             let mem: MemoryOperand = if rm.0 == 0b110 {
-                let address = u16::from_le_bytes([
-                    *iter.next().expect("lower address byte"),
-                    *iter.next().expect("higher address byte"),
-                ]);
+                let address = u16::from_le_bytes([iter.next_byte()?, iter.next_byte()?]);
                 MemoryOperand::direct_address(address, w)
             } else {
                 // No displacement
@@ -156,18 +537,17 @@ fn parse_mod_reg_rm_instr(iter: &mut Iter<u8>, b: u8, w: Wide) -> Result<(Operan
         // Memory mode, 8-bit displacement
         0b01 => {
             let reg = Register::from_reg_w(reg, w);
-            let displacement = i16::from_le_bytes([*iter.next().expect("displacement byte"), 0]);
-            let mem = MemoryOperand::from_mod_rm(mod_, rm, w)?.with_displacement(displacement);
+            let displacement = iter.next_byte()? as i8 as i16;
+            let mem = MemoryOperand::from_mod_rm(mod_, rm, w)?
+                .with_displacement(displacement, MemorySize::Byte);
             (Operand::Register(reg), Operand::Memory(mem))
         }
         // Memory mode, 16-bit displacement
         0b10 => {
             let reg = Register::from_reg_w(reg, w);
-            let displacement = i16::from_le_bytes([
-                *iter.next().expect("lower displacement byte"),
-                *iter.next().expect("higher displacement byte"),
-            ]);
-            let mem = MemoryOperand::from_mod_rm(mod_, rm, w)?.with_displacement(displacement);
+            let displacement = i16::from_le_bytes([iter.next_byte()?, iter.next_byte()?]);
+            let mem = MemoryOperand::from_mod_rm(mod_, rm, w)?
+                .with_displacement(displacement, MemorySize::Word);
             (Operand::Register(reg), Operand::Memory(mem))
         }
         // Register Mode (no displacement)
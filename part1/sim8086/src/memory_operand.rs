@@ -6,6 +6,10 @@ use anyhow::Result;
 pub enum MemorySize {
     Byte,
     Word,
+    Dword,
+    Qword,
+    /// 80-bit operand of the 8087 (extended real or packed BCD)
+    Tword,
 }
 
 impl std::fmt::Display for MemorySize {
@@ -13,6 +17,9 @@ impl std::fmt::Display for MemorySize {
         match self {
             MemorySize::Byte => write!(f, "byte"),
             MemorySize::Word => write!(f, "word"),
+            MemorySize::Dword => write!(f, "dword"),
+            MemorySize::Qword => write!(f, "qword"),
+            MemorySize::Tword => write!(f, "tword"),
         }
     }
 }
@@ -26,6 +33,9 @@ pub struct MemoryOperand {
     /// Displacement value for this memory operand
     pub displacement: Option<i16>,
 
+    /// Width of the displacement as encoded in the instruction stream
+    pub displacement_size: Option<MemorySize>,
+
     /// Size of memory read
     pub size: Option<MemorySize>,
 
@@ -45,6 +55,7 @@ impl MemoryOperand {
         Self {
             registers: [None; 2],
             displacement: None,
+            displacement_size: None,
             size: Some(size),
             address: Some(addr),
         }
@@ -79,9 +90,8 @@ impl MemoryOperand {
                 registers[0] = Some(Register::Di);
             }
             0b110 => {
-                // 16 bit displacement
-
-                todo!("direct address")
+                // With mod 00 this is a direct address instead, see `direct_address`
+                registers[0] = Some(Register::Bp);
             }
             0b111 => {
                 registers[0] = Some(Register::Bx);
@@ -100,11 +110,13 @@ impl MemoryOperand {
             size: Some(size),
             address: None,
             displacement: None,
+            displacement_size: None,
         })
     }
 
-    pub fn with_displacement(&mut self, displacement: i16) -> Self {
+    pub fn with_displacement(&mut self, displacement: i16, size: MemorySize) -> Self {
         self.displacement = Some(displacement);
+        self.displacement_size = Some(size);
         *self
     }
 }
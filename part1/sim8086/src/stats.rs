@@ -0,0 +1,106 @@
+use crate::instruction::{Instruction, Operand};
+use crate::memory_operand::{MemoryOperand, MemorySize};
+use crate::register::Register;
+use std::collections::BTreeMap;
+
+/// Static statistics gathered over every decoded instruction of a binary
+#[derive(Default)]
+pub struct Stats {
+    /// Number of decoded instructions, including the ones that could not be decoded
+    pub instructions: u64,
+    /// Number of occurrences per mnemonic
+    pub mnemonics: BTreeMap<&'static str, u64>,
+    /// Number of operands per addressing mode
+    pub addressing_modes: BTreeMap<&'static str, u64>,
+    /// Number of instructions per encoded length in bytes
+    pub lengths: BTreeMap<usize, u64>,
+}
+
+impl Stats {
+    /// Count a decoded instruction that was `len` bytes long
+    pub fn record(&mut self, inst: &Instruction, len: usize) {
+        self.instructions += 1;
+        *self.mnemonics.entry(inst.mnemonic()).or_default() += 1;
+        *self.lengths.entry(len).or_default() += 1;
+
+        for operand in inst.operands().iter().flatten() {
+            *self
+                .addressing_modes
+                .entry(addressing_mode(operand))
+                .or_default() += 1;
+        }
+    }
+
+    /// Count an instruction that could not be decoded, its length is unknown
+    pub fn record_unknown(&mut self) {
+        self.instructions += 1;
+        *self.mnemonics.entry("unknown").or_default() += 1;
+    }
+}
+
+/// Classify an operand by how its value is found, memory operands include the displacement width
+fn addressing_mode(operand: &Operand) -> &'static str {
+    match operand {
+        Operand::Register(_) => "register",
+        Operand::Immediate(_) => "immediate",
+        Operand::FpuRegister(_) => "fpu register",
+        Operand::CodeAddress(_) => "relative",
+        Operand::FarAddress { .. } => "far direct",
+        Operand::Memory(MemoryOperand {
+            address: Some(_), ..
+        }) => "direct",
+        Operand::Memory(mem) => match (mem.registers, mem.displacement_size) {
+            ([Some(_), Some(_)], None) => "based indexed",
+            ([Some(_), Some(_)], Some(MemorySize::Byte)) => "based indexed + disp8",
+            ([Some(_), Some(_)], Some(_)) => "based indexed + disp16",
+            ([Some(Register::Bx | Register::Bp), None], None) => "based",
+            ([Some(Register::Bx | Register::Bp), None], Some(MemorySize::Byte)) => "based + disp8",
+            ([Some(Register::Bx | Register::Bp), None], Some(_)) => "based + disp16",
+            (_, None) => "indexed",
+            (_, Some(MemorySize::Byte)) => "indexed + disp8",
+            (_, Some(_)) => "indexed + disp16",
+        },
+    }
+}
+
+impl std::fmt::Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        writeln!(f, "Instructions: {}", self.instructions)?;
+
+        writeln!(f, "\nMnemonics")?;
+        writeln!(f, "=====================")?;
+        write_histogram(f, sorted_by_count(&self.mnemonics), self.instructions)?;
+
+        let operands = self.addressing_modes.values().sum();
+        writeln!(f, "\nAddressing modes")?;
+        writeln!(f, "=====================")?;
+        write_histogram(f, sorted_by_count(&self.addressing_modes), operands)?;
+
+        writeln!(f, "\nInstruction lengths (bytes)")?;
+        writeln!(f, "=====================")?;
+        let lengths = self
+            .lengths
+            .iter()
+            .map(|(len, count)| (len.to_string(), *count));
+        write_histogram(f, lengths, self.lengths.values().sum())
+    }
+}
+
+/// Entries ordered from most to least frequent, ties are kept in key order
+fn sorted_by_count<K: ToString>(counts: &BTreeMap<K, u64>) -> Vec<(String, u64)> {
+    let mut entries: Vec<_> = counts.iter().map(|(k, v)| (k.to_string(), *v)).collect();
+    entries.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    entries
+}
+
+fn write_histogram(
+    f: &mut std::fmt::Formatter<'_>,
+    entries: impl IntoIterator<Item = (String, u64)>,
+    total: u64,
+) -> Result<(), std::fmt::Error> {
+    for (label, count) in entries {
+        let percent = 100.0 * count as f64 / total as f64;
+        writeln!(f, "{label:<24} {count:>8} {percent:>6.2}%")?;
+    }
+    Ok(())
+}
@@ -0,0 +1,211 @@
+use crate::instruction::{Instruction, Operand};
+use crate::memory_operand::MemorySize;
+use anyhow::bail;
+
+/// Assembler dialect used to print decoded instructions
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Syntax {
+    /// `word [bp + di - 0x25]`, `0x` prefixed immediates
+    #[default]
+    Nasm,
+    /// `word ptr [bp+di-25h]`, `h` suffixed immediates, segments outside the brackets
+    Masm,
+}
+
+impl std::str::FromStr for Syntax {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "nasm" => Ok(Syntax::Nasm),
+            "masm" => Ok(Syntax::Masm),
+            _ => bail!("unknown syntax '{s}', expected nasm or masm"),
+        }
+    }
+}
+
+impl Syntax {
+    /// Directive at the top of a listing that selects 16-bit code
+    pub fn header(self) -> &'static str {
+        match self {
+            Syntax::Nasm => "bits 16",
+            Syntax::Masm => ".8086",
+        }
+    }
+
+    /// Directive that places the following bytes at `address`, where `origin` is the address of
+    /// the first region. NASM only accepts a single `org`, so later regions are reached by padding.
+    pub fn place_at(self, origin: usize, address: usize) -> Option<String> {
+        match self {
+            _ if address == 0 => None,
+            Syntax::Nasm if address == origin => Some(format!("org {address:#x}")),
+            Syntax::Nasm => Some(format!("times {:#x} - ($ - $$) db 0", address - origin)),
+            Syntax::Masm => Some(format!("org {}", self.hex(address as u16, 0))),
+        }
+    }
+
+    /// Hexadecimal number, zero padded to at least `digits` digits
+    pub fn hex(self, value: u16, digits: usize) -> Hex {
+        Hex {
+            value,
+            digits,
+            syntax: self,
+        }
+    }
+}
+
+/// A number printed in the hexadecimal notation of a syntax
+pub struct Hex {
+    value: u16,
+    digits: usize,
+    syntax: Syntax,
+}
+
+impl std::fmt::Display for Hex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        let Hex {
+            value,
+            digits,
+            syntax,
+        } = *self;
+
+        match syntax {
+            Syntax::Nasm => write!(f, "{value:#0width$x}", width = digits + 2),
+            Syntax::Masm => {
+                // MASM reads a number starting with a letter as a symbol
+                let significant = (16 - value.leading_zeros() as usize).div_ceil(4).max(1);
+                let leading_digit = value >> (4 * (significant - 1));
+                if significant >= digits && leading_digit >= 0xa {
+                    write!(f, "0")?;
+                }
+                write!(f, "{value:0digits$X}h")
+            }
+        }
+    }
+}
+
+/// An instruction or operand together with the syntax to print it in
+pub struct Formatted<'a, T> {
+    value: &'a T,
+    syntax: Syntax,
+}
+
+impl Instruction {
+    /// Display the instruction in the given assembler syntax
+    pub fn display(&self, syntax: Syntax) -> Formatted<'_, Instruction> {
+        Formatted {
+            value: self,
+            syntax,
+        }
+    }
+}
+
+impl Operand {
+    /// Display the operand in the given assembler syntax
+    pub fn display(&self, syntax: Syntax) -> Formatted<'_, Operand> {
+        Formatted {
+            value: self,
+            syntax,
+        }
+    }
+}
+
+impl std::fmt::Display for Formatted<'_, Instruction> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        let syntax = self.syntax;
+
+        // MASM only knows the breakpoint interrupt as the regular int with vector 3
+        if let (Instruction::Int3, Syntax::Masm) = (self.value, syntax) {
+            return write!(f, "int 3");
+        }
+
+        // The base of aam/aad is a divisor/multiplier, it reads best in decimal
+        if let Instruction::Aam { base } | Instruction::Aad { base } = self.value {
+            if *base != 10 {
+                return write!(f, "{} {base}", self.value.mnemonic());
+            }
+        }
+
+        write!(f, "{}", self.value.mnemonic())?;
+
+        match self.value.operands() {
+            [Some(op1), Some(op2)] => {
+                write!(f, " {}, {}", op1.display(syntax), op2.display(syntax))
+            }
+            [Some(op), None] => write!(f, " {}", op.display(syntax)),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl std::fmt::Display for Formatted<'_, Operand> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        let syntax = self.syntax;
+
+        match self.value {
+            Operand::Register(reg) => write!(f, "{reg}"),
+            Operand::Immediate(imm) => write!(f, "{}", syntax.hex(*imm as u16, 0)),
+            Operand::FpuRegister(i) => match syntax {
+                Syntax::Nasm => write!(f, "st{i}"),
+                Syntax::Masm => write!(f, "st({i})"),
+            },
+            Operand::CodeAddress(address) => write!(f, "{}", syntax.hex(*address, 0)),
+            Operand::FarAddress { segment, offset } => {
+                write!(f, "{}:{}", syntax.hex(*segment, 0), syntax.hex(*offset, 0))
+            }
+
+            Operand::Memory(mem) => {
+                if let Some(size) = mem.size {
+                    match (syntax, size) {
+                        (Syntax::Nasm, _) => write!(f, "{size} ")?,
+                        (Syntax::Masm, MemorySize::Tword) => write!(f, "tbyte ptr ")?,
+                        (Syntax::Masm, _) => write!(f, "{size} ptr ")?,
+                    }
+                }
+
+                // If there is a direct memory address, write it and return. MASM would read a
+                // bare bracketed number as an immediate, so the (default) segment is spelled out.
+                if let Some(address) = mem.address {
+                    if syntax == Syntax::Masm {
+                        write!(f, "ds:")?;
+                    }
+                    return write!(f, "[{}]", syntax.hex(address, 0));
+                }
+
+                // NASM puts spaces around the operators inside the brackets, MASM does not
+                let (plus, minus) = match syntax {
+                    Syntax::Nasm => (" + ", " - "),
+                    Syntax::Masm => ("+", "-"),
+                };
+
+                // Open the memory bracket
+                write!(f, "[")?;
+
+                // If the operand has a register(s), write them
+                if let Some(reg1) = mem.registers[0] {
+                    write!(f, "{reg1}")?;
+
+                    if let Some(reg2) = mem.registers[1] {
+                        write!(f, "{plus}{reg2}")?;
+                    }
+                }
+
+                // Write the displacement if it exists
+                if let Some(displacement) = mem.displacement {
+                    if displacement != 0 {
+                        let sign = if displacement.is_negative() {
+                            minus
+                        } else {
+                            plus
+                        };
+
+                        // Regardless, print the absolute value of the offset
+                        write!(f, "{sign}{}", syntax.hex(displacement.unsigned_abs(), 0))?;
+                    }
+                }
+
+                write!(f, "]")
+            }
+        }
+    }
+}
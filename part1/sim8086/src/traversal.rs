@@ -0,0 +1,134 @@
+use crate::image::Image;
+use crate::instruction::Instruction;
+use crate::syntax::Syntax;
+use crate::{decode_instruction, ByteStream};
+use anyhow::Result;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Write};
+
+/// Number of bytes written per `db` line
+const DATA_BYTES_PER_LINE: usize = 16;
+
+/// An instruction reached by following the control flow, with its encoded length
+struct Decoded {
+    inst: Instruction,
+    len: usize,
+}
+
+/// Decode only the bytes that are reachable as code from `entries`, following jumps, calls and
+/// fallthroughs. Paths end at instructions that do not fall through, at indirect or far
+/// branches, and at bytes that do not decode.
+fn traverse(image: &Image, entries: &[u16]) -> BTreeMap<usize, Decoded> {
+    let mut code = BTreeMap::new();
+    let mut pending: Vec<usize> = entries.iter().map(|&entry| entry as usize).collect();
+
+    while let Some(address) = pending.pop() {
+        if code.contains_key(&address) {
+            continue;
+        }
+        let Some(bytes) = image.bytes_at(address) else {
+            continue;
+        };
+
+        let mut iter = ByteStream::from_slice(bytes, address);
+
+        let Ok(Some(inst)) = decode_instruction(&mut iter) else {
+            continue;
+        };
+
+        if let Some(target) = inst.branch_target() {
+            pending.push(target as usize);
+        }
+        if inst.falls_through() {
+            pending.push(iter.position);
+        }
+
+        let len = iter.position - address;
+        code.insert(address, Decoded { inst, len });
+    }
+
+    code
+}
+
+/// Write a listing of `image` where everything reachable from `entries` is decoded as code and
+/// all other bytes are emitted as `db` data. Branch targets get a label named after their address.
+pub fn write_listing<W: Write>(
+    image: &Image,
+    entries: &[u16],
+    syntax: Syntax,
+    writer: W,
+) -> Result<()> {
+    let mut writer = io::BufWriter::new(writer);
+    let code = traverse(image, entries);
+
+    // Code reached through a jump into the middle of another instruction overlaps it and cannot
+    // be written, only the instructions found in a linear walk over the code get labels
+    let mut starts = BTreeSet::new();
+    for region in &image.regions {
+        let mut address = region.address;
+        while address < region.address + region.bytes.len() {
+            match code.get(&address) {
+                Some(decoded) => {
+                    starts.insert(address);
+                    address += decoded.len;
+                }
+                None => address += 1,
+            }
+        }
+    }
+
+    let labels: BTreeSet<usize> = code
+        .values()
+        .filter_map(|decoded| decoded.inst.branch_target())
+        .map(|target| target as usize)
+        .filter(|target| starts.contains(target))
+        .collect();
+
+    writeln!(writer, "{}\n", syntax.header())?;
+
+    let origin = image.regions.first().map_or(0, |region| region.address);
+    for region in &image.regions {
+        if let Some(directive) = syntax.place_at(origin, region.address) {
+            writeln!(writer, "{directive}")?;
+        }
+
+        let end = region.address + region.bytes.len();
+        let mut address = region.address;
+        while address < end {
+            if let Some(decoded) = code.get(&address) {
+                if labels.contains(&address) {
+                    writeln!(writer, "{}:", label(address))?;
+                }
+
+                match decoded.inst.branch_target().map(|target| target as usize) {
+                    Some(target) if labels.contains(&target) => {
+                        writeln!(writer, "{} {}", decoded.inst.mnemonic(), label(target))?
+                    }
+                    _ => writeln!(writer, "{}", decoded.inst.display(syntax))?,
+                }
+
+                address += decoded.len;
+                continue;
+            }
+
+            // Data runs until the next instruction
+            let data_end = starts.range(address..end).next().copied().unwrap_or(end);
+            let data = &region.bytes[address - region.address..data_end - region.address];
+            for line in data.chunks(DATA_BYTES_PER_LINE) {
+                let data: Vec<String> = line
+                    .iter()
+                    .map(|&b| syntax.hex(u16::from(b), 2).to_string())
+                    .collect();
+                writeln!(writer, "db {}", data.join(", "))?;
+            }
+            address = data_end;
+        }
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+fn label(address: usize) -> String {
+    format!("label_{address:04x}")
+}
@@ -12,7 +12,7 @@ fn compare(path: &str) -> Result<(), Box<dyn std::error::Error>> {
 
     cmd.assert()
         .success()
-        .stdout(predicate::str::is_match(expected_output).unwrap());
+        .stdout(predicate::str::contains(expected_output));
 
     Ok(())
 }
@@ -40,3 +40,289 @@ fn listing_0040_single_register_mov() -> Result<(), Box<dyn std::error::Error>>
     compare("tests/resources/listing_0040_challenge_movs")?;
     Ok(())
 }
+
+#[test]
+fn port_io() -> Result<(), Box<dyn std::error::Error>> {
+    compare("tests/resources/port_io")?;
+    Ok(())
+}
+
+#[test]
+fn adjust_and_control() -> Result<(), Box<dyn std::error::Error>> {
+    compare("tests/resources/adjust_and_control")?;
+    Ok(())
+}
+
+#[test]
+fn decode_from_stdin() -> Result<(), Box<dyn std::error::Error>> {
+    let expected_output = fs::read_to_string("tests/resources/port_io.asm")?;
+
+    let mut cmd = assert_cmd::Command::cargo_bin("sim8086")?;
+    cmd.arg("-")
+        .write_stdin(fs::read("tests/resources/port_io")?);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(expected_output));
+
+    Ok(())
+}
+
+#[test]
+fn fpu_escape() -> Result<(), Box<dyn std::error::Error>> {
+    compare("tests/resources/fpu_escape")?;
+    Ok(())
+}
+
+#[test]
+fn stats() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("sim8086")?;
+    cmd.arg("--stats").arg("tests/resources/adjust_and_control");
+
+    cmd.assert().success().stdout(
+        predicate::str::contains("Instructions: 25")
+            .and(predicate::str::is_match(r"xchg +3 +12\.00%").unwrap())
+            .and(predicate::str::is_match(r"based indexed \+ disp8 +1 ").unwrap())
+            .and(predicate::str::is_match(r"\n1 +17 +68\.00%").unwrap()),
+    );
+
+    Ok(())
+}
+
+#[test]
+fn recursive_traversal() -> Result<(), Box<dyn std::error::Error>> {
+    let expected_output = fs::read_to_string("tests/resources/code_and_data.asm")?;
+
+    let mut cmd = Command::cargo_bin("sim8086")?;
+    cmd.arg("--recursive").arg("tests/resources/code_and_data");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(expected_output));
+
+    Ok(())
+}
+
+#[test]
+fn recursive_traversal_undecodable_fallthrough() -> Result<(), Box<dyn std::error::Error>> {
+    // The bytes after the call are not decoded, they end that path and are listed as data
+    let expected_output = fs::read_to_string("tests/resources/undecodable_fallthrough.asm")?;
+
+    let mut cmd = Command::cargo_bin("sim8086")?;
+    cmd.arg("--recursive")
+        .arg("tests/resources/undecodable_fallthrough");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(expected_output));
+
+    Ok(())
+}
+
+#[test]
+fn recursive_traversal_word_immediate() -> Result<(), Box<dyn std::error::Error>> {
+    // The call is only found if the two immediate bytes of the mov before it are skipped
+    let expected_output = fs::read_to_string("tests/resources/word_immediate.asm")?;
+
+    let mut cmd = Command::cargo_bin("sim8086")?;
+    cmd.arg("--recursive").arg("tests/resources/word_immediate");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(expected_output));
+
+    Ok(())
+}
+
+#[test]
+fn recursive_traversal_extra_entry() -> Result<(), Box<dyn std::error::Error>> {
+    // Nothing branches to the ret, it is decoded because of --entry. The start at 0 still is.
+    let mut cmd = Command::cargo_bin("sim8086")?;
+    cmd.arg("--recursive")
+        .arg("--entry")
+        .arg("0x4")
+        .arg("--hex")
+        .arg("eb fe 90 90 c3");
+
+    cmd.assert().success().stdout(predicate::str::contains(
+        "label_0000:\njmp label_0000\ndb 0x90, 0x90\nret\n",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn entry_needs_recursive() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("sim8086")?;
+    cmd.arg("--entry").arg("0x4").arg("--hex").arg("c3");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--entry only applies"));
+
+    Ok(())
+}
+
+#[test]
+fn masm_syntax() -> Result<(), Box<dyn std::error::Error>> {
+    let expected_output = fs::read_to_string("tests/resources/fpu_escape.masm.asm")?;
+
+    let mut cmd = Command::cargo_bin("sim8086")?;
+    cmd.arg("--syntax")
+        .arg("masm")
+        .arg("tests/resources/fpu_escape");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(expected_output));
+
+    Ok(())
+}
+
+#[test]
+fn hex_text_input() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("sim8086")?;
+    cmd.arg("--hex").arg("89 d9 0x88,0xe5");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("mov cx, bx\nmov ch, ah\n"));
+
+    Ok(())
+}
+
+#[test]
+fn hex_text_immediates() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("sim8086")?;
+    cmd.arg("--hex").arg("b8 34 12 b1 0c a1 78 56");
+
+    cmd.assert().success().stdout(predicate::str::contains(
+        "mov ax, 0x1234\nmov cl, 0xc\nmov ax, word [0x5678]\n",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn intel_hex_input() -> Result<(), Box<dyn std::error::Error>> {
+    let expected_output = fs::read_to_string("tests/resources/load_address.hex.asm")?;
+
+    // The start address record makes the traversal begin at 0x100 instead of the first byte
+    let mut cmd = Command::cargo_bin("sim8086")?;
+    cmd.arg("--ihex")
+        .arg("--recursive")
+        .arg("tests/resources/load_address.hex");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(expected_output));
+
+    Ok(())
+}
+
+#[test]
+fn intel_hex_linear_address() -> Result<(), Box<dyn std::error::Error>> {
+    // Extended and start linear address records, as written by most tools
+    let expected_output = fs::read_to_string("tests/resources/linear_address.hex.asm")?;
+
+    let mut cmd = Command::cargo_bin("sim8086")?;
+    cmd.arg("--ihex")
+        .arg("--recursive")
+        .arg("tests/resources/linear_address.hex");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(expected_output));
+
+    Ok(())
+}
+
+#[test]
+fn lea_register_operand_is_invalid() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("sim8086")?;
+    cmd.arg("--hex").arg("8d c3");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid register operand"));
+
+    Ok(())
+}
+
+#[test]
+fn stats_skip_unknown_opcodes() -> Result<(), Box<dyn std::error::Error>> {
+    // 0x0f (pop cs) is not decoded, the mov instructions around it still are
+    let mut cmd = Command::cargo_bin("sim8086")?;
+    cmd.arg("--stats").arg("--hex").arg("89 d9 0f 88 e5");
+
+    cmd.assert().success().stdout(
+        predicate::str::contains("Instructions: 3")
+            .and(predicate::str::is_match(r"mov +2 +66\.67%").unwrap())
+            .and(predicate::str::is_match(r"unknown +1 +33\.33%").unwrap())
+            .and(predicate::str::is_match(r"\n2 +2 +100\.00%").unwrap()),
+    );
+
+    Ok(())
+}
+
+#[test]
+fn recursive_rejects_more_than_a_segment() -> Result<(), Box<dyn std::error::Error>> {
+    // Branch targets are 16-bit, bytes past 64 KiB cannot be reached
+    let mut cmd = assert_cmd::Command::cargo_bin("sim8086")?;
+    cmd.arg("--recursive")
+        .arg("-")
+        .write_stdin(vec![0x90; 0x1_0001]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("do not fit in a 64 KiB segment"));
+
+    Ok(())
+}
+
+#[test]
+fn truncated_instruction_is_an_error() -> Result<(), Box<dyn std::error::Error>> {
+    // A mov without its mod/reg/rm byte and a call with half of its displacement
+    for bytes in ["89", "e8 00"] {
+        let mut cmd = Command::cargo_bin("sim8086")?;
+        cmd.arg("--hex").arg(bytes);
+
+        cmd.assert().failure().stderr(predicate::str::contains(
+            "ends in the middle of an instruction",
+        ));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn stats_count_truncated_instruction() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("sim8086")?;
+    cmd.arg("--stats").arg("--hex").arg("89 d9 e8 00");
+
+    cmd.assert().success().stdout(
+        predicate::str::contains("Instructions: 2")
+            .and(predicate::str::is_match(r"unknown +1 +50\.00%").unwrap()),
+    );
+
+    Ok(())
+}
+
+#[test]
+fn stats_word_immediate_length() -> Result<(), Box<dyn std::error::Error>> {
+    // mov ax, 0x1234 and the call are 3 bytes each, the two rets 1 byte
+    let mut cmd = Command::cargo_bin("sim8086")?;
+    cmd.arg("--stats")
+        .arg("--hex")
+        .arg("b8 34 12 e8 01 00 c3 c3");
+
+    cmd.assert().success().stdout(
+        predicate::str::contains("Instructions: 4")
+            .and(predicate::str::contains("unknown").not())
+            .and(predicate::str::is_match(r"ret +2 +50\.00%").unwrap())
+            .and(predicate::str::is_match(r"\n1 +2 +50\.00%").unwrap())
+            .and(predicate::str::is_match(r"\n3 +2 +50\.00%").unwrap()),
+    );
+
+    Ok(())
+}
@@ -0,0 +1,27 @@
+bits 16
+
+daa
+das
+aaa
+aas
+aam
+aad
+aam 16
+cbw
+cwd
+xchg bx, cx
+xchg ah, byte [bx]
+xchg ax, bx
+nop
+lea ax, [bx + si + 0x4]
+lds bx, [0x1234]
+les di, [bp + si]
+lahf
+sahf
+clc
+stc
+cmc
+cld
+std
+cli
+sti
@@ -0,0 +1,16 @@
+bits 16
+
+call label_0008
+jmp label_000b
+db 0xde, 0xad, 0xbe
+label_0008:
+mov cx, bx
+ret
+label_000b:
+je label_000f
+in al, 0x40
+label_000f:
+cli
+label_0010:
+jmp label_0010
+db 0x00, 0x01, 0x02
@@ -0,0 +1,26 @@
+bits 16
+
+wait
+fninit
+fld dword [0x1000]
+fld qword [bx + 0x8]
+fld tword [bx]
+fmul qword [bx]
+fadd st0, st1
+fcom st1
+fsub st1, st0
+fdivp st1, st0
+faddp st1, st0
+fsin
+fcos
+fsqrt
+fxch st1
+fstp qword [si + 0x18]
+fistp qword [si]
+fiadd dword [bx]
+fimul word [bx]
+fnstcw word [bx + 0x2]
+fnstsw ax
+fcompp
+fld1
+fldz
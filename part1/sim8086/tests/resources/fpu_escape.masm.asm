@@ -0,0 +1,26 @@
+.8086
+
+wait
+fninit
+fld dword ptr ds:[1000h]
+fld qword ptr [bx+8h]
+fld tbyte ptr [bx]
+fmul qword ptr [bx]
+fadd st(0), st(1)
+fcom st(1)
+fsub st(1), st(0)
+fdivp st(1), st(0)
+faddp st(1), st(0)
+fsin
+fcos
+fsqrt
+fxch st(1)
+fstp qword ptr [si+18h]
+fistp qword ptr [si]
+fiadd dword ptr [bx]
+fimul word ptr [bx]
+fnstcw word ptr [bx+2h]
+fnstsw ax
+fcompp
+fld1
+fldz
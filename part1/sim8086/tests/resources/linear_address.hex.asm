@@ -0,0 +1,8 @@
+bits 16
+
+org 0x100
+jmp label_0103
+db 0xcc
+label_0103:
+mov cx, bx
+ret
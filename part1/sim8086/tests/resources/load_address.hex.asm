@@ -0,0 +1,10 @@
+bits 16
+
+org 0x100
+jmp label_0103
+db 0xcc
+label_0103:
+mov cx, bx
+ret
+times 0x100 - ($ - $$) db 0
+db 0x88, 0xe5, 0xc3
@@ -0,0 +1,10 @@
+bits 16
+
+in al, 0x40
+in ax, 0x60
+in al, dx
+in ax, dx
+out 0x43, al
+out 0x20, ax
+out dx, al
+out dx, ax
@@ -0,0 +1,7 @@
+bits 16
+
+call label_0005
+db 0xc6, 0x07
+label_0005:
+mov ax, word [bp + 0x4]
+ret
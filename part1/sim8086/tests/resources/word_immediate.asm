@@ -0,0 +1,8 @@
+bits 16
+
+mov ax, 0x1234
+call label_0007
+ret
+label_0007:
+mov ax, word [bp - 0x2]
+ret
@@ -1,5 +1,7 @@
 use anyhow::Result;
 use haversine::haversine;
+use json_parser::parser::JsonValue;
+use json_parser::{Format, JsonWriter};
 use rand_casey::{random_in_range, seed, RandomSeries};
 use std::fs::File;
 use std::io::prelude::*;
@@ -97,29 +99,22 @@ fn write_point_pairs_json(point_pairs: &Vec<PointPair>) -> std::io::Result<()> {
         point_pairs.len()
     ))?);
 
-    // header
-    write!(writer, "{{\"pairs\":[")?;
-
-    if !point_pairs.is_empty() {
-        // Handle first element separately as to not have a trailing comma (json doesn't accept that)
-        let first_pair = &point_pairs[0];
-        write!(
-            writer,
-            "\n\t{{\"x0\":{},\"y0\":{},\"x1\":{},\"y1\":{}}}",
-            first_pair.x0, first_pair.y0, first_pair.x1, first_pair.y1
-        )?;
-
-        for pp in &point_pairs[1..] {
-            write!(
-                writer,
-                ",\n\t{{\"x0\":{},\"y0\":{},\"x1\":{},\"y1\":{}}}",
-                pp.x0, pp.y0, pp.x1, pp.y1
-            )?;
+    // each pair is written as soon as it is formatted, the document is never held in memory
+    let mut json = JsonWriter::new(&mut writer, Format::Compact);
+    json.begin_object()?;
+    json.key("pairs")?;
+    json.begin_array()?;
+    for pp in point_pairs {
+        json.begin_object()?;
+        for (key, n) in [("x0", pp.x0), ("y0", pp.y0), ("x1", pp.x1), ("y1", pp.y1)] {
+            json.key(key)?;
+            json.value(&JsonValue::Number(n))?;
         }
+        json.end_object()?;
     }
-
-    // footer
-    writeln!(writer, "\n]}}")?;
+    json.end_array()?;
+    json.end_object()?;
+    writeln!(writer)?;
     writer.flush()?;
 
     Ok(())
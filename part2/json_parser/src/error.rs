@@ -0,0 +1,157 @@
+/// Number of bytes of input shown on either side of an error
+const SNIPPET_CONTEXT: usize = 20;
+
+/// What went wrong while parsing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonErrorKind {
+    /// The input ended before the value was complete
+    UnexpectedEnd,
+    /// A token that cannot appear at this point, e.g. a ':' as array value
+    UnexpectedToken,
+    /// A character that does not start any token
+    UnknownCharacter,
+    UnclosedString,
+    InvalidEscape,
+    InvalidUnicodeEscape,
+    /// A `\u` escape of a UTF-16 surrogate without its other half
+    UnpairedSurrogate,
+    /// A control character that has to be escaped in strict mode
+    ControlCharacter,
+    InvalidUtf8,
+    InvalidNumber,
+    ExpectedKey,
+    ExpectedColon,
+    ExpectedObjectEnd,
+    ExpectedArrayEnd,
+    /// More input after the root value in strict mode
+    TrailingContent,
+    /// Reading the input failed
+    Io(std::io::ErrorKind),
+}
+
+impl std::fmt::Display for JsonErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let message = match self {
+            JsonErrorKind::UnexpectedEnd => "unexpected end of input",
+            JsonErrorKind::UnexpectedToken => "unexpected token",
+            JsonErrorKind::UnknownCharacter => "unknown character",
+            JsonErrorKind::UnclosedString => "unclosed string",
+            JsonErrorKind::InvalidEscape => "invalid escape sequence",
+            JsonErrorKind::InvalidUnicodeEscape => "invalid unicode escape",
+            JsonErrorKind::UnpairedSurrogate => "unpaired surrogate in unicode escape",
+            JsonErrorKind::ControlCharacter => "unescaped control character in string",
+            JsonErrorKind::InvalidUtf8 => "invalid UTF-8 sequence",
+            JsonErrorKind::InvalidNumber => "invalid number",
+            JsonErrorKind::ExpectedKey => "expected string as object key",
+            JsonErrorKind::ExpectedColon => "expected ':' after object key",
+            JsonErrorKind::ExpectedObjectEnd => "expected ',' or '}' after object member",
+            JsonErrorKind::ExpectedArrayEnd => "expected ',' or ']' after array value",
+            JsonErrorKind::TrailingContent => "unexpected content after the root value",
+            JsonErrorKind::Io(kind) => return write!(f, "I/O error: {kind}"),
+        };
+        f.write_str(message)
+    }
+}
+
+/// A parse error together with where in the input it happened
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonError {
+    pub kind: JsonErrorKind,
+    /// Byte offset into the input
+    pub offset: usize,
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based column, counted in characters
+    pub column: usize,
+    /// The input around the error, on the same line
+    pub snippet: String,
+}
+
+impl JsonError {
+    /// An error at `offset` that still has to be located in the input, see [`JsonError::locate`]
+    pub(crate) fn new(kind: JsonErrorKind, offset: usize) -> Self {
+        Self {
+            kind,
+            offset,
+            line: 0,
+            column: 0,
+            snippet: String::new(),
+        }
+    }
+
+    /// Fill in the line, column and snippet from the input the offset points into. Only done
+    /// once parsing failed, as it scans the input from the start.
+    pub(crate) fn locate(mut self, input: &[u8]) -> Self {
+        let offset = self.offset.min(input.len());
+        let before = &input[..offset];
+
+        let line_start = before
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |i| i + 1);
+        let line_end = input[offset..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(input.len(), |i| offset + i);
+
+        self.line = before.iter().filter(|&&b| b == b'\n').count() + 1;
+        // count the bytes that start a UTF-8 character
+        self.column = before[line_start..]
+            .iter()
+            .filter(|&&b| b & 0xc0 != 0x80)
+            .count()
+            + 1;
+        self.snippet = snippet(&input[line_start..line_end], offset - line_start);
+
+        self
+    }
+
+    /// An error whose line and column are already known, with only part of the input around it
+    /// at hand. `at` is the position of the error in `window`.
+    pub(crate) fn in_window(
+        kind: JsonErrorKind,
+        offset: usize,
+        (line, column): (usize, usize),
+        window: &[u8],
+        at: usize,
+    ) -> Self {
+        let at = at.min(window.len());
+        let line_start = window[..at]
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |i| i + 1);
+        let line_end = window[at..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(window.len(), |i| at + i);
+
+        Self {
+            kind,
+            offset,
+            line,
+            column,
+            snippet: snippet(&window[line_start..line_end], at - line_start),
+        }
+    }
+}
+
+/// The part of a single line around position `at`
+fn snippet(line: &[u8], at: usize) -> String {
+    let start = at.saturating_sub(SNIPPET_CONTEXT);
+    let end = line.len().min(at + SNIPPET_CONTEXT);
+    String::from_utf8_lossy(&line[start..end])
+        .trim()
+        .to_string()
+}
+
+impl std::fmt::Display for JsonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} at line {}, column {}: {}",
+            self.kind, self.line, self.column, self.snippet
+        )
+    }
+}
+
+impl std::error::Error for JsonError {}
@@ -1,25 +1,61 @@
+mod error;
 pub mod parser;
+mod serializer;
+mod stream;
 mod tokenizer;
 
+pub use crate::error::{JsonError, JsonErrorKind};
 use crate::parser::{JsonValue, Parser};
+pub use crate::serializer::{to_json_string, write_json, Format, JsonWriter};
+pub use crate::stream::{JsonEvent, JsonReader};
 use crate::tokenizer::Tokenizer;
 
-pub fn parse_json_str(input: &str) -> Result<JsonValue, String> {
-    let tokenizer = Tokenizer::new(input.as_bytes());
-    let mut parser = Parser::new(tokenizer);
-    parser.parse_value()
+/// How closely the input has to follow RFC 8259
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Reject everything RFC 8259 does not allow. When false, extra commas, content after the
+    /// root value and loosely formatted numbers are accepted (see the readme).
+    pub strict: bool,
+}
+
+impl ParseOptions {
+    pub fn strict() -> Self {
+        Self { strict: true }
+    }
+
+    pub fn lenient() -> Self {
+        Self { strict: false }
+    }
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self::strict()
+    }
+}
+
+/// Parse a JSON document following RFC 8259 strictly
+pub fn parse_json_str(input: &str) -> Result<JsonValue<'_>, JsonError> {
+    parse_json_str_with(input, ParseOptions::default())
+}
+
+pub fn parse_json_str_with(input: &str, options: ParseOptions) -> Result<JsonValue<'_>, JsonError> {
+    let tokenizer = Tokenizer::new(input.as_bytes(), options);
+    let mut parser = Parser::new(tokenizer, options);
+    parser.parse().map_err(|e| e.locate(input.as_bytes()))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::borrow::Cow;
 
     #[test]
     fn parse_empty_string() {
         let json_str = "";
         let res = parse_json_str(json_str);
         assert!(res.is_err(), "Expected an error for empty string");
-        assert_eq!(res.unwrap_err(), "Unexpected token");
+        assert_eq!(res.unwrap_err().kind, JsonErrorKind::UnexpectedEnd);
     }
 
     #[test]
@@ -86,10 +122,10 @@ mod tests {
         assert_eq!(values.len(), 2);
         let first_expected_pair = JsonValue::Object {
             kv: vec![
-                ("x0", JsonValue::Number(12.5f64)),
-                ("y0", JsonValue::Number(24.5f64)),
-                ("x1", JsonValue::Number(50.5f64)),
-                ("y1", JsonValue::Number(37.5f64)),
+                ("x0".into(), JsonValue::Number(12.5f64)),
+                ("y0".into(), JsonValue::Number(24.5f64)),
+                ("x1".into(), JsonValue::Number(50.5f64)),
+                ("y1".into(), JsonValue::Number(37.5f64)),
             ]
             .into_iter()
             .collect(),
@@ -97,10 +133,10 @@ mod tests {
 
         let second_expected_pair = JsonValue::Object {
             kv: vec![
-                ("x0", JsonValue::Number(12.25f64)),
-                ("y0", JsonValue::Number(22.25f64)),
-                ("x1", JsonValue::Number(-17.25f64)),
-                ("y1", JsonValue::Number(3.525e-9f64)),
+                ("x0".into(), JsonValue::Number(12.25f64)),
+                ("y0".into(), JsonValue::Number(22.25f64)),
+                ("x1".into(), JsonValue::Number(-17.25f64)),
+                ("y1".into(), JsonValue::Number(3.525e-9f64)),
             ]
             .into_iter()
             .collect(),
@@ -109,4 +145,326 @@ mod tests {
         assert_eq!(&values[0], &first_expected_pair, "First pair mismatch");
         assert_eq!(&values[1], &second_expected_pair, "Second pair mismatch");
     }
+
+    #[test]
+    fn parse_string_without_escapes_borrows() {
+        let result = parse_json_str(r#"["plain"]"#).unwrap();
+
+        if let JsonValue::Array { values } = result {
+            assert!(matches!(
+                &values[0],
+                JsonValue::String(Cow::Borrowed("plain"))
+            ));
+        } else {
+            panic!("Expected array");
+        }
+    }
+
+    #[test]
+    fn parse_string_escapes() {
+        let json_str = r#"{"a\"b": "\"\\\/\b\f\n\r\t\u00e9\ud83d\ude00"}"#;
+        let result = parse_json_str(json_str).unwrap();
+
+        let expected = JsonValue::Object {
+            kv: vec![(
+                "a\"b".into(),
+                JsonValue::String("\"\\/\u{8}\u{c}\n\r\té😀".into()),
+            )],
+        };
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn parse_invalid_escapes() {
+        assert!(parse_json_str(r#"["\x"]"#).is_err(), "Unknown escape");
+        assert!(
+            parse_json_str(r#"["\u12"]"#).is_err(),
+            "Short unicode escape"
+        );
+        assert!(
+            parse_json_str(r#"["\ud83d"]"#).is_err(),
+            "Unpaired high surrogate"
+        );
+        assert!(
+            parse_json_str(r#"["\ude00"]"#).is_err(),
+            "Unpaired low surrogate"
+        );
+    }
+
+    #[test]
+    fn strict_rejects_extra_commas() {
+        for json_str in ["[1,]", "[,1]", "[1,,2]", "[1 2]", r#"{"a":1,}"#] {
+            assert!(
+                parse_json_str(json_str).is_err(),
+                "Expected {json_str} to fail"
+            );
+            assert!(
+                parse_json_str_with(json_str, ParseOptions::lenient()).is_ok(),
+                "Expected {json_str} to pass leniently"
+            );
+        }
+    }
+
+    #[test]
+    fn strict_rejects_trailing_content() {
+        assert!(parse_json_str("{} {}").is_err());
+        assert!(parse_json_str_with("{} {}", ParseOptions::lenient()).is_ok());
+    }
+
+    #[test]
+    fn strict_rejects_malformed_numbers() {
+        for json_str in [
+            "[01]", "[-]", "[1.]", "[.5]", "[1e]", "[1e+]", "[-01.5]", "[1.5.2]", "[+1]",
+            "[1e400]", "[-1e400]",
+        ] {
+            assert!(
+                parse_json_str(json_str).is_err(),
+                "Expected {json_str} to fail"
+            );
+        }
+    }
+
+    #[test]
+    fn strict_accepts_rfc_8259() {
+        assert_eq!(parse_json_str("-0.5e+3"), Ok(JsonValue::Number(-500.0)));
+        assert_eq!(parse_json_str(" \t\r\nnull"), Ok(JsonValue::Null));
+
+        let result = parse_json_str(r#"[0, 10, 1E2, -1.25e-1, {"a": [true, false]}]"#).unwrap();
+        if let JsonValue::Array { values } = result {
+            assert_eq!(values.len(), 5);
+            assert_eq!(values[2], JsonValue::Number(100.0));
+        } else {
+            panic!("Expected array");
+        }
+    }
+
+    #[test]
+    fn strict_rejects_control_characters_in_strings() {
+        assert!(parse_json_str("[\"a\tb\"]").is_err());
+        assert!(parse_json_str_with("[\"a\tb\"]", ParseOptions::lenient()).is_ok());
+    }
+
+    #[test]
+    fn error_location() {
+        let json_str = "{\n    \"pairs\": [\n        {\"x0\": 1.5, \"y0\" 2.5}\n    ]\n}";
+        let err = parse_json_str(json_str).unwrap_err();
+
+        assert_eq!(err.kind, JsonErrorKind::ExpectedColon);
+        assert_eq!(err.offset, json_str.find("2.5").unwrap());
+        assert_eq!((err.line, err.column), (3, 26));
+        assert_eq!(err.snippet, r#"{"x0": 1.5, "y0" 2.5}"#);
+        assert_eq!(
+            err.to_string(),
+            r#"expected ':' after object key at line 3, column 26: {"x0": 1.5, "y0" 2.5}"#
+        );
+    }
+
+    #[test]
+    fn error_from_tokenizer() {
+        let err = parse_json_str("[1, 2, é]").unwrap_err();
+        assert_eq!(
+            (err.kind, err.offset, err.column),
+            (JsonErrorKind::UnknownCharacter, 7, 8)
+        );
+
+        let err = parse_json_str_with("[1, 2-]", ParseOptions::lenient()).unwrap_err();
+        assert_eq!((err.kind, err.offset), (JsonErrorKind::InvalidNumber, 4));
+
+        let err = parse_json_str(r#"{"a": [1, 2"#).unwrap_err();
+        assert_eq!((err.kind, err.offset), (JsonErrorKind::UnexpectedEnd, 11));
+    }
+
+    #[test]
+    fn serialize_compact_and_pretty() {
+        let value = JsonValue::Object {
+            kv: vec![
+                ("empty".into(), JsonValue::Array { values: vec![] }),
+                (
+                    "values".into(),
+                    JsonValue::Array {
+                        values: vec![
+                            JsonValue::Number(1.0),
+                            JsonValue::Boolean(true),
+                            JsonValue::Null,
+                        ],
+                    },
+                ),
+                ("name".into(), JsonValue::String("a\"b\\c\n\u{1}é".into())),
+            ],
+        };
+
+        assert_eq!(
+            to_json_string(&value, Format::Compact).unwrap(),
+            r#"{"empty":[],"values":[1,true,null],"name":"a\"b\\c\n\u0001é"}"#
+        );
+        assert_eq!(
+            to_json_string(&value, Format::Pretty { indent: 2 }).unwrap(),
+            "{\n  \"empty\": [],\n  \"values\": [\n    1,\n    true,\n    null\n  ],\n  \"name\": \"a\\\"b\\\\c\\n\\u0001é\"\n}"
+        );
+    }
+
+    #[test]
+    fn serialize_numbers() {
+        let numbers = [
+            0.0,
+            -0.0,
+            0.1,
+            -17.25,
+            3.525e-9,
+            1e300,
+            f64::MAX,
+            f64::MIN_POSITIVE,
+            5e-324,
+        ];
+        for n in numbers {
+            let json = to_json_string(&JsonValue::Number(n), Format::Compact).unwrap();
+            let parsed = parse_json_str(&json).unwrap();
+            assert_eq!(parsed, JsonValue::Number(n), "{n} serialized as {json}");
+        }
+
+        assert_eq!(
+            to_json_string(&JsonValue::Number(0.1), Format::Compact).unwrap(),
+            "0.1"
+        );
+        assert_eq!(
+            to_json_string(&JsonValue::Number(1e300), Format::Compact).unwrap(),
+            "1e300"
+        );
+        assert!(to_json_string(&JsonValue::Number(f64::NAN), Format::Compact).is_err());
+    }
+
+    #[test]
+    fn serialize_round_trip() {
+        let json_str = r#"
+        {
+            "pairs": [
+                {"x0": 12.5, "y0": -24.125, "x1": 1e-7, "y1": 3.525e-9},
+                {"x0": 0, "y0": 22.25, "x1": -17.25, "y1": 1.7976931348623157e308}
+            ],
+            "escaped": "tab\there \"quoted\" é 😀 \u001f",
+            "flags": [true, false, null],
+            "empty": {}
+        }
+        "#;
+        let value = parse_json_str(json_str).unwrap();
+
+        for format in [Format::Compact, Format::Pretty { indent: 4 }] {
+            let serialized = to_json_string(&value, format).unwrap();
+            assert_eq!(parse_json_str(&serialized).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn serialize_incrementally() {
+        let value = parse_json_str(r#"{"pairs": [{"x0": 1.5, "y0": -2}, {}], "n": null}"#).unwrap();
+
+        for format in [Format::Compact, Format::Pretty { indent: 2 }] {
+            let mut buf = Vec::new();
+            let mut writer = JsonWriter::new(&mut buf, format);
+            writer.begin_object().unwrap();
+            writer.key("pairs").unwrap();
+            writer.begin_array().unwrap();
+            writer.begin_object().unwrap();
+            writer.key("x0").unwrap();
+            writer.value(&JsonValue::Number(1.5)).unwrap();
+            writer.key("y0").unwrap();
+            writer.value(&JsonValue::Number(-2.0)).unwrap();
+            writer.end_object().unwrap();
+            writer.value(&JsonValue::Object { kv: vec![] }).unwrap();
+            writer.end_array().unwrap();
+            writer.key("n").unwrap();
+            writer.value(&JsonValue::Null).unwrap();
+            writer.end_object().unwrap();
+
+            let written = String::from_utf8(buf).unwrap();
+            assert_eq!(written, to_json_string(&value, format).unwrap());
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "needs a key")]
+    fn serialize_value_without_key() {
+        let mut writer = JsonWriter::new(Vec::new(), Format::Compact);
+        writer.begin_object().unwrap();
+        let _ = writer.value(&JsonValue::Null);
+    }
+
+    #[test]
+    fn stream_events() {
+        let json_str =
+            r#"{"pairs": [{"x0": 12.5, "y0": -1e-3}, []], "s": "a\"😀", "t": [true, false, null]}"#;
+        let events: Vec<JsonEvent> = JsonReader::new(json_str.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                JsonEvent::StartObject,
+                JsonEvent::Key("pairs".to_string()),
+                JsonEvent::StartArray,
+                JsonEvent::StartObject,
+                JsonEvent::Key("x0".to_string()),
+                JsonEvent::Number(12.5),
+                JsonEvent::Key("y0".to_string()),
+                JsonEvent::Number(-1e-3),
+                JsonEvent::EndObject,
+                JsonEvent::StartArray,
+                JsonEvent::EndArray,
+                JsonEvent::EndArray,
+                JsonEvent::Key("s".to_string()),
+                JsonEvent::String("a\"😀".to_string()),
+                JsonEvent::Key("t".to_string()),
+                JsonEvent::StartArray,
+                JsonEvent::Boolean(true),
+                JsonEvent::Boolean(false),
+                JsonEvent::Null,
+                JsonEvent::EndArray,
+                JsonEvent::EndObject,
+            ]
+        );
+    }
+
+    #[test]
+    fn stream_with_small_buffer() {
+        // tokens and escapes cross the boundaries of a buffer this small
+        let json_str = r#"[12345.6789, "escaped é and 😀", {"key": true}]"#;
+        let small: Vec<_> = JsonReader::with_capacity(3, json_str.as_bytes()).collect();
+        let large: Vec<_> = JsonReader::new(json_str.as_bytes()).collect();
+
+        assert_eq!(small, large);
+        assert_eq!(small[1], Ok(JsonEvent::Number(12345.6789)));
+        assert_eq!(
+            small[2],
+            Ok(JsonEvent::String("escaped é and 😀".to_string()))
+        );
+    }
+
+    #[test]
+    fn stream_errors() {
+        for json_str in [
+            "[1,]",
+            "[1 2]",
+            r#"{"a":1,}"#,
+            "{} {}",
+            "[01]",
+            "[1",
+            r#"{"a" 1}"#,
+            "[1e400]",
+        ] {
+            let result: Result<Vec<_>, _> = JsonReader::new(json_str.as_bytes()).collect();
+            assert!(result.is_err(), "Expected {json_str} to fail");
+        }
+
+        let json_str = "{\n  \"pairs\": [\n    {\"x0\": 1.5,, \"y0\": 2.5}\n  ]\n}";
+        let err = JsonReader::with_capacity(8, json_str.as_bytes())
+            .find_map(Result::err)
+            .unwrap();
+        let expected = parse_json_str(json_str).unwrap_err();
+        assert_eq!(
+            (err.kind, err.offset),
+            (JsonErrorKind::ExpectedKey, expected.offset)
+        );
+        assert_eq!((err.line, err.column), (expected.line, expected.column));
+    }
 }
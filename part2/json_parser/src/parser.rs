@@ -1,11 +1,19 @@
+use crate::error::{JsonError, JsonErrorKind};
 use crate::tokenizer::JsonToken;
+use crate::ParseOptions;
+use std::borrow::Cow;
+use std::ops::Range;
 
 #[derive(Debug, PartialEq)]
 pub enum JsonValue<'a> {
-    Object { kv: Vec<(&'a str, JsonValue<'a>)> },
-    Array { values: Vec<JsonValue<'a>> },
+    Object {
+        kv: Vec<(Cow<'a, str>, JsonValue<'a>)>,
+    },
+    Array {
+        values: Vec<JsonValue<'a>>,
+    },
     Number(f64),
-    String(&'a str),
+    String(Cow<'a, str>),
     Boolean(bool),
     Null,
 }
@@ -13,34 +21,54 @@ pub enum JsonValue<'a> {
 // Parser takes an iterator, which in this case is the tokenizer
 pub(crate) struct Parser<'a, I>
 where
-    I: Iterator<Item = std::result::Result<JsonToken<'a>, String>>,
+    I: Iterator<Item = Result<(JsonToken<'a>, Range<usize>), JsonError>>,
 {
     tokens: I,
-    current_token: Option<std::result::Result<JsonToken<'a>, String>>,
+    current_token: Option<Result<JsonToken<'a>, JsonError>>,
+    /// Byte offset of the current token, or of the end of the last token once they ran out
+    offset: usize,
+    /// Byte offset of the end of the last token
+    last_end: usize,
+    options: ParseOptions,
 }
 
 impl<'a, I> Parser<'a, I>
 where
-    I: Iterator<Item = std::result::Result<JsonToken<'a>, String>>,
+    I: Iterator<Item = Result<(JsonToken<'a>, Range<usize>), JsonError>>,
 {
-    pub(crate) fn new(tokens: I) -> Self {
+    pub(crate) fn new(tokens: I, options: ParseOptions) -> Self {
         let mut parser = Parser {
             tokens,
             current_token: None,
+            offset: 0,
+            last_end: 0,
+            options,
         };
 
         // set the current token to the first token
         parser.advance();
         parser
     }
-    pub(crate) fn parse_value(&mut self) -> Result<JsonValue<'a>, String> {
+
+    /// Parse the root value of a document
+    pub(crate) fn parse(&mut self) -> Result<JsonValue<'a>, JsonError> {
+        let value = self.parse_value()?;
+
+        // the lenient parser ignores anything following the root value
+        if self.options.strict && self.current_token.is_some() {
+            return Err(self.error(JsonErrorKind::TrailingContent));
+        }
+        Ok(value)
+    }
+
+    pub(crate) fn parse_value(&mut self) -> Result<JsonValue<'a>, JsonError> {
+        if let Some(s) = self.take_string() {
+            return Ok(JsonValue::String(s));
+        }
+
         match self.current_token {
             Some(Ok(JsonToken::BeginObject)) => self.parse_object(),
             Some(Ok(JsonToken::BeginArray)) => self.parse_array(),
-            Some(Ok(JsonToken::String(s))) => {
-                self.advance();
-                Ok(JsonValue::String(s))
-            }
             Some(Ok(JsonToken::Number(n))) => {
                 self.advance();
                 Ok(JsonValue::Number(n))
@@ -53,15 +81,50 @@ where
                 self.advance();
                 Ok(JsonValue::Null)
             }
-            _ => Err("Unexpected token".to_string()),
+            _ => Err(self.error(JsonErrorKind::UnexpectedToken)),
         }
     }
 
     fn advance(&mut self) {
-        self.current_token = self.tokens.next();
+        self.current_token = match self.tokens.next() {
+            Some(Ok((token, span))) => {
+                self.offset = span.start;
+                self.last_end = span.end;
+                Some(Ok(token))
+            }
+            Some(Err(e)) => Some(Err(e)),
+            None => {
+                self.offset = self.last_end;
+                None
+            }
+        };
+    }
+
+    /// Error for a current token that is not what was expected. A token that failed to tokenize
+    /// reports its own error and running out of tokens is reported as the end of the input.
+    fn error(&mut self, kind: JsonErrorKind) -> JsonError {
+        match self.current_token.take() {
+            Some(Err(e)) => e,
+            Some(Ok(_)) => JsonError::new(kind, self.offset),
+            None => JsonError::new(JsonErrorKind::UnexpectedEnd, self.offset),
+        }
     }
 
-    fn parse_object(&mut self) -> Result<JsonValue<'a>, String> {
+    /// Consume the current token if it is a string, moving the string out of it
+    fn take_string(&mut self) -> Option<Cow<'a, str>> {
+        match self.current_token.take() {
+            Some(Ok(JsonToken::String(s))) => {
+                self.advance();
+                Some(s)
+            }
+            token => {
+                self.current_token = token;
+                None
+            }
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<JsonValue<'a>, JsonError> {
         if let Some(Ok(JsonToken::BeginObject)) = self.current_token {
             self.advance(); // consume '{'
 
@@ -72,6 +135,13 @@ where
                 // Check if there's a comma to continue parsing
                 if let Some(Ok(JsonToken::Comma)) = self.current_token {
                     self.advance(); // consume ','
+
+                    // strictly, a comma has to be followed by another member
+                    if self.options.strict
+                        && !matches!(self.current_token, Some(Ok(JsonToken::String(_))))
+                    {
+                        return Err(self.error(JsonErrorKind::ExpectedKey));
+                    }
                     continue;
                 } else {
                     break;
@@ -81,50 +151,63 @@ where
             if let Some(Ok(JsonToken::EndObject)) = self.current_token {
                 self.advance(); // consume '}'
                 Ok(JsonValue::Object { kv })
+            } else if kv.is_empty() {
+                Err(self.error(JsonErrorKind::ExpectedKey))
             } else {
-                Err(format!(
-                    "Expected '}}' to close object, but found {:?}",
-                    self.current_token
-                ))
+                Err(self.error(JsonErrorKind::ExpectedObjectEnd))
             }
         } else {
-            Err("Expected '{' to start an object.".to_string())
+            Err(self.error(JsonErrorKind::UnexpectedToken))
         }
     }
 
-    fn parse_kv(&mut self) -> Result<(&'a str, JsonValue<'a>), String> {
+    fn parse_kv(&mut self) -> Result<(Cow<'a, str>, JsonValue<'a>), JsonError> {
         // check if the current token is a string and peek the next token to see if it's a ':'
 
-        if let Some(Ok(JsonToken::String(key))) = self.current_token {
-            self.advance(); // consume key
-
+        if let Some(key) = self.take_string() {
             if let Some(Ok(JsonToken::Colon)) = self.current_token {
                 self.advance(); // consume ':'
                 let value = self.parse_value()?;
                 Ok((key, value))
             } else {
-                Err("Expected ':' after object key.".to_string())
+                Err(self.error(JsonErrorKind::ExpectedColon))
             }
         } else {
-            Err("Expected string as object key.".to_string())
+            Err(self.error(JsonErrorKind::ExpectedKey))
         }
     }
 
-    fn parse_array(&mut self) -> Result<JsonValue<'a>, String> {
+    fn parse_array(&mut self) -> Result<JsonValue<'a>, JsonError> {
         // consume current token '['
         self.advance();
 
         let mut values = Vec::new();
-        while let Some(Ok(token)) = &self.current_token {
-            match token {
-                JsonToken::EndArray => {
-                    break;
-                }
-                JsonToken::Comma => {
-                    self.advance();
-                }
-                _ => {
+        if self.options.strict {
+            // values are separated by exactly one comma, without a trailing one
+            if !matches!(self.current_token, Some(Ok(JsonToken::EndArray))) {
+                loop {
                     values.push(self.parse_value()?);
+
+                    if let Some(Ok(JsonToken::Comma)) = self.current_token {
+                        self.advance(); // consume ','
+                    } else {
+                        break;
+                    }
+                }
+            }
+        } else {
+            // commas are skipped wherever they appear
+            while let Some(Ok(token)) = &self.current_token {
+                match token {
+                    JsonToken::EndArray => {
+                        break;
+                    }
+                    JsonToken::Comma => {
+                        self.advance();
+                    }
+                    _ => {
+                        values.push(self.parse_value()?);
+                    }
                 }
             }
         }
@@ -133,7 +216,7 @@ where
             self.advance(); // consume ']'
             Ok(JsonValue::Array { values })
         } else {
-            Err("Expected ']' to close array.".to_string())
+            Err(self.error(JsonErrorKind::ExpectedArrayEnd))
         }
     }
 }
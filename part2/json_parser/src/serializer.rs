@@ -0,0 +1,224 @@
+use crate::parser::JsonValue;
+use std::io::{self, Write};
+
+/// Layout of serialized JSON
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Everything on a single line without any whitespace
+    Compact,
+    /// Every member and array value on its own line, nested `indent` spaces deeper than its parent
+    Pretty { indent: usize },
+}
+
+/// Write `value` as JSON. Numbers that are NaN or infinite cannot be represented and fail with
+/// `io::ErrorKind::InvalidInput`.
+pub fn write_json<W: Write>(value: &JsonValue, writer: &mut W, format: Format) -> io::Result<()> {
+    JsonWriter::new(writer, format).value(value)
+}
+
+/// Serialize `value` into a `String`, see [`write_json`]
+pub fn to_json_string(value: &JsonValue, format: Format) -> io::Result<String> {
+    let mut buf = Vec::new();
+    write_json(value, &mut buf, format)?;
+    Ok(String::from_utf8(buf).expect("serialized JSON is UTF-8"))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Container {
+    Object,
+    Array,
+}
+
+/// Writes a JSON document piece by piece, so it never has to exist as a [`JsonValue`] as a
+/// whole. Commas, and the line breaks of the pretty format, are added in between as needed.
+///
+/// Object members are written as a [`JsonWriter::key`] followed by its value. Calls that do not
+/// form valid JSON, e.g. a value in an object without a key, panic.
+pub struct JsonWriter<W> {
+    writer: W,
+    format: Format,
+    /// Objects and arrays that have been started but not ended, and whether they have any
+    /// members or values yet
+    stack: Vec<(Container, bool)>,
+    /// A key has been written, its value comes next
+    after_key: bool,
+}
+
+impl<W: Write> JsonWriter<W> {
+    pub fn new(writer: W, format: Format) -> Self {
+        Self {
+            writer,
+            format,
+            stack: Vec::new(),
+            after_key: false,
+        }
+    }
+
+    pub fn begin_object(&mut self) -> io::Result<()> {
+        self.begin(Container::Object, b"{")
+    }
+
+    pub fn end_object(&mut self) -> io::Result<()> {
+        self.end(Container::Object, b"}")
+    }
+
+    pub fn begin_array(&mut self) -> io::Result<()> {
+        self.begin(Container::Array, b"[")
+    }
+
+    pub fn end_array(&mut self) -> io::Result<()> {
+        self.end(Container::Array, b"]")
+    }
+
+    /// Name of the next member of the innermost object
+    pub fn key(&mut self, key: &str) -> io::Result<()> {
+        assert!(
+            matches!(self.stack.last(), Some((Container::Object, _))) && !self.after_key,
+            "a key can only start a member of an object"
+        );
+        self.separate()?;
+        write_string(key, &mut self.writer)?;
+        match self.format {
+            Format::Compact => self.writer.write_all(b":")?,
+            Format::Pretty { .. } => self.writer.write_all(b": ")?,
+        }
+        self.after_key = true;
+        Ok(())
+    }
+
+    /// Write a complete value, see [`write_json`]
+    pub fn value(&mut self, value: &JsonValue) -> io::Result<()> {
+        match value {
+            JsonValue::Object { kv } => {
+                self.begin_object()?;
+                for (key, value) in kv {
+                    self.key(key)?;
+                    self.value(value)?;
+                }
+                self.end_object()
+            }
+            JsonValue::Array { values } => {
+                self.begin_array()?;
+                for value in values {
+                    self.value(value)?;
+                }
+                self.end_array()
+            }
+            JsonValue::Number(n) => self.scalar(|w| write_number(*n, w)),
+            JsonValue::String(s) => self.scalar(|w| write_string(s, w)),
+            JsonValue::Boolean(true) => self.scalar(|w| w.write_all(b"true")),
+            JsonValue::Boolean(false) => self.scalar(|w| w.write_all(b"false")),
+            JsonValue::Null => self.scalar(|w| w.write_all(b"null")),
+        }
+    }
+
+    fn scalar(&mut self, write: impl FnOnce(&mut W) -> io::Result<()>) -> io::Result<()> {
+        self.before_value()?;
+        write(&mut self.writer)
+    }
+
+    fn begin(&mut self, container: Container, bracket: &[u8]) -> io::Result<()> {
+        self.before_value()?;
+        self.writer.write_all(bracket)?;
+        self.stack.push((container, false));
+        Ok(())
+    }
+
+    fn end(&mut self, container: Container, bracket: &[u8]) -> io::Result<()> {
+        let open = self.stack.pop();
+        assert!(
+            open.map(|(c, _)| c) == Some(container) && !self.after_key,
+            "end does not match the innermost object or array"
+        );
+        // empty objects and arrays stay on a single line
+        if let Some((_, true)) = open {
+            write_newline(&mut self.writer, self.format, self.stack.len())?;
+        }
+        self.writer.write_all(bracket)
+    }
+
+    /// Start a value, which in an object has to follow its key
+    fn before_value(&mut self) -> io::Result<()> {
+        if self.after_key {
+            self.after_key = false;
+            return Ok(());
+        }
+        assert!(
+            !matches!(self.stack.last(), Some((Container::Object, _))),
+            "a value in an object needs a key first"
+        );
+        self.separate()
+    }
+
+    /// Whatever has to come between the previous member or value and the next one
+    fn separate(&mut self) -> io::Result<()> {
+        let depth = self.stack.len();
+        let Some((_, has_entries)) = self.stack.last_mut() else {
+            return Ok(());
+        };
+        if *has_entries {
+            self.writer.write_all(b",")?;
+        }
+        *has_entries = true;
+        write_newline(&mut self.writer, self.format, depth)
+    }
+}
+
+/// Start a new line indented to `depth`, only in the pretty format
+fn write_newline<W: Write>(writer: &mut W, format: Format, depth: usize) -> io::Result<()> {
+    match format {
+        Format::Compact => Ok(()),
+        Format::Pretty { indent } => write!(writer, "\n{:width$}", "", width = indent * depth),
+    }
+}
+
+/// Write the shortest representation that parses back to the same `f64`. Rust's formatting
+/// already picks the shortest digits, very large and small magnitudes use an exponent so they do
+/// not expand into hundreds of zeros.
+fn write_number<W: Write>(n: f64, writer: &mut W) -> io::Result<()> {
+    if !n.is_finite() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{n} cannot be written as a JSON number"),
+        ));
+    }
+
+    let magnitude = n.abs();
+    if magnitude == 0.0 || (1e-5..1e16).contains(&magnitude) {
+        write!(writer, "{n}")
+    } else {
+        write!(writer, "{n:e}")
+    }
+}
+
+/// Write a quoted string, escaping quotes, backslashes and control characters
+fn write_string<W: Write>(s: &str, writer: &mut W) -> io::Result<()> {
+    writer.write_all(b"\"")?;
+
+    // copy the runs between characters that need escaping as they are
+    let mut start = 0;
+    for (i, b) in s.bytes().enumerate() {
+        let escape: &[u8] = match b {
+            b'"' => b"\\\"",
+            b'\\' => b"\\\\",
+            b'\n' => b"\\n",
+            b'\r' => b"\\r",
+            b'\t' => b"\\t",
+            0x08 => b"\\b",
+            0x0c => b"\\f",
+            0x00..=0x1f => {
+                writer.write_all(&s.as_bytes()[start..i])?;
+                write!(writer, "\\u{b:04x}")?;
+                start = i + 1;
+                continue;
+            }
+            _ => continue,
+        };
+        writer.write_all(&s.as_bytes()[start..i])?;
+        writer.write_all(escape)?;
+        start = i + 1;
+    }
+    writer.write_all(&s.as_bytes()[start..])?;
+
+    writer.write_all(b"\"")
+}
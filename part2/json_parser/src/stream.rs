@@ -0,0 +1,369 @@
+use crate::error::{JsonError, JsonErrorKind};
+use crate::tokenizer::{strict_number_len, unescape};
+use std::io::{self, Read};
+
+/// Default number of input bytes held in memory at once
+const DEFAULT_CAPACITY: usize = 64 * 1024;
+
+/// A piece of a JSON document, in the order it appears in the input
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonEvent {
+    StartObject,
+    EndObject,
+    StartArray,
+    EndArray,
+    /// Name of the next object member, its value follows as the next event(s)
+    Key(String),
+    String(String),
+    Number(f64),
+    Boolean(bool),
+    Null,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Container {
+    Object,
+    Array,
+}
+
+/// What the grammar allows at the current point of the document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Expect {
+    /// A value, at the root, after a ':' or after a ',' in an array
+    Value,
+    /// The first value of an array or its ']'
+    ValueOrEnd,
+    /// The first key of an object or its '}'
+    KeyOrEnd,
+    /// A key after a ',' in an object
+    Key,
+    Colon,
+    /// A ',' or the end of the innermost container, after one of its values
+    CommaOrEnd,
+    /// Nothing but whitespace after the root value
+    Done,
+}
+
+/// Where in the input a byte is
+#[derive(Debug, Clone, Copy)]
+struct Position {
+    offset: usize,
+    line: usize,
+    column: usize,
+}
+
+/// Pull parser that reads a JSON document from any `io::Read` and yields it as a sequence of
+/// [`JsonEvent`]s, without building the document in memory. Only a fixed size buffer of input is
+/// held, besides the string or number being read and one entry per open object or array.
+///
+/// The input has to follow RFC 8259 strictly. Iteration stops after the first error.
+pub struct JsonReader<R> {
+    reader: R,
+    buf: Box<[u8]>,
+    /// Next byte to read in `buf`
+    pos: usize,
+    /// Number of bytes in `buf` read from `reader`
+    filled: usize,
+    /// Position of `buf[pos]` in the input
+    position: Position,
+    /// Objects and arrays that have been started but not ended
+    stack: Vec<Container>,
+    expect: Expect,
+    failed: bool,
+}
+
+impl<R: Read> JsonReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY, reader)
+    }
+
+    /// Reader that keeps at most `capacity` bytes of input in memory
+    pub fn with_capacity(capacity: usize, reader: R) -> Self {
+        assert!(capacity > 0, "buffer capacity must be at least one byte");
+        Self {
+            reader,
+            buf: vec![0; capacity].into_boxed_slice(),
+            pos: 0,
+            filled: 0,
+            position: Position {
+                offset: 0,
+                line: 1,
+                column: 1,
+            },
+            stack: Vec::new(),
+            expect: Expect::Value,
+            failed: false,
+        }
+    }
+
+    fn next_event(&mut self) -> Result<Option<JsonEvent>, JsonError> {
+        loop {
+            // RFC 8259 only allows space, tab, line feed and carriage return between tokens
+            while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek()? {
+                self.bump();
+            }
+
+            let start = self.position;
+            let innermost = self.stack.last().copied();
+            let event = match (self.expect, self.peek()?) {
+                (Expect::Done, None) => return Ok(None),
+                (Expect::Done, Some(_)) => {
+                    return Err(self.error(JsonErrorKind::TrailingContent, start))
+                }
+                (_, None) => return Err(self.error(JsonErrorKind::UnexpectedEnd, start)),
+
+                (Expect::KeyOrEnd | Expect::CommaOrEnd, Some(b'}'))
+                    if innermost == Some(Container::Object) =>
+                {
+                    self.bump();
+                    self.stack.pop();
+                    self.end_value();
+                    JsonEvent::EndObject
+                }
+                (Expect::ValueOrEnd | Expect::CommaOrEnd, Some(b']'))
+                    if innermost == Some(Container::Array) =>
+                {
+                    self.bump();
+                    self.stack.pop();
+                    self.end_value();
+                    JsonEvent::EndArray
+                }
+                (Expect::CommaOrEnd, Some(b',')) => {
+                    self.bump();
+                    self.expect = match innermost {
+                        Some(Container::Object) => Expect::Key,
+                        _ => Expect::Value,
+                    };
+                    continue;
+                }
+                (Expect::CommaOrEnd, Some(_)) => {
+                    let kind = match innermost {
+                        Some(Container::Object) => JsonErrorKind::ExpectedObjectEnd,
+                        _ => JsonErrorKind::ExpectedArrayEnd,
+                    };
+                    return Err(self.error(kind, start));
+                }
+
+                (Expect::KeyOrEnd | Expect::Key, Some(b'"')) => {
+                    let key = self.string()?;
+                    self.expect = Expect::Colon;
+                    JsonEvent::Key(key)
+                }
+                (Expect::KeyOrEnd | Expect::Key, Some(_)) => {
+                    return Err(self.error(JsonErrorKind::ExpectedKey, start))
+                }
+                (Expect::Colon, Some(b':')) => {
+                    self.bump();
+                    self.expect = Expect::Value;
+                    continue;
+                }
+                (Expect::Colon, Some(_)) => {
+                    return Err(self.error(JsonErrorKind::ExpectedColon, start))
+                }
+
+                (Expect::Value | Expect::ValueOrEnd, Some(byte)) => self.value(byte)?,
+            };
+
+            return Ok(Some(event));
+        }
+    }
+
+    /// Read the value starting with `byte`, or only its start for objects and arrays
+    fn value(&mut self, byte: u8) -> Result<JsonEvent, JsonError> {
+        let start = self.position;
+
+        let event = match byte {
+            b'{' => {
+                self.bump();
+                self.stack.push(Container::Object);
+                self.expect = Expect::KeyOrEnd;
+                return Ok(JsonEvent::StartObject);
+            }
+            b'[' => {
+                self.bump();
+                self.stack.push(Container::Array);
+                self.expect = Expect::ValueOrEnd;
+                return Ok(JsonEvent::StartArray);
+            }
+            b'"' => JsonEvent::String(self.string()?),
+            b'-' | b'0'..=b'9' => JsonEvent::Number(self.number()?),
+            b'a'..=b'z' => match self.take_while(|b| b.is_ascii_lowercase())?.as_slice() {
+                b"true" => JsonEvent::Boolean(true),
+                b"false" => JsonEvent::Boolean(false),
+                b"null" => JsonEvent::Null,
+                _ => return Err(self.error(JsonErrorKind::UnknownCharacter, start)),
+            },
+            b']' | b'}' | b',' | b':' => {
+                return Err(self.error(JsonErrorKind::UnexpectedToken, start))
+            }
+            _ => return Err(self.error(JsonErrorKind::UnknownCharacter, start)),
+        };
+
+        self.end_value();
+        Ok(event)
+    }
+
+    /// A value is complete, what follows depends on the container it is in
+    fn end_value(&mut self) {
+        self.expect = if self.stack.is_empty() {
+            Expect::Done
+        } else {
+            Expect::CommaOrEnd
+        };
+    }
+
+    /// Read a string starting at its opening quote, decoding its escapes
+    fn string(&mut self) -> Result<String, JsonError> {
+        let start = self.position;
+        self.bump(); // consume '"'
+
+        let mut bytes = Vec::new();
+        loop {
+            let at = self.position;
+            match self.next_byte()? {
+                Some(b'"') => break,
+                Some(b'\\') => {
+                    let Some(kind) = self.next_byte()? else {
+                        return Err(self.error(JsonErrorKind::UnclosedString, start));
+                    };
+                    let len = if kind == b'u' { 6 } else { 2 };
+                    let mut escape = vec![b'\\', kind];
+                    self.read_into(&mut escape, len)?;
+
+                    // a high surrogate has to be followed by a `\u` escape of the low one
+                    let code_unit = std::str::from_utf8(&escape[2..])
+                        .ok()
+                        .and_then(|digits| u32::from_str_radix(digits, 16).ok());
+                    if len == 6 && matches!(code_unit, Some(0xd800..=0xdbff)) {
+                        self.read_into(&mut escape, 12)?;
+                    }
+
+                    let (ch, _) = unescape(&escape).map_err(|kind| self.error(kind, at))?;
+                    bytes.extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                Some(b) if b < 0x20 => {
+                    return Err(self.error(JsonErrorKind::ControlCharacter, at));
+                }
+                Some(b) => bytes.push(b),
+                None => return Err(self.error(JsonErrorKind::UnclosedString, start)),
+            }
+        }
+
+        String::from_utf8(bytes).map_err(|_| self.error(JsonErrorKind::InvalidUtf8, start))
+    }
+
+    fn number(&mut self) -> Result<f64, JsonError> {
+        let start = self.position;
+        let bytes = self
+            .take_while(|b| b.is_ascii_digit() || matches!(b, b'.' | b'+' | b'-' | b'e' | b'E'))?;
+
+        match strict_number_len(&bytes) {
+            Some(len) if len == bytes.len() => {
+                let num_str = std::str::from_utf8(&bytes).expect("number is ASCII");
+                // numbers too large for an f64 would become infinity, which JSON cannot hold
+                match num_str.parse::<f64>() {
+                    Ok(num) if num.is_finite() => Ok(num),
+                    _ => Err(self.error(JsonErrorKind::InvalidNumber, start)),
+                }
+            }
+            _ => Err(self.error(JsonErrorKind::InvalidNumber, start)),
+        }
+    }
+
+    /// Consume bytes as long as they match `pred`
+    fn take_while(&mut self, pred: impl Fn(u8) -> bool) -> Result<Vec<u8>, JsonError> {
+        let mut bytes = Vec::new();
+        while let Some(b) = self.peek()? {
+            if !pred(b) {
+                break;
+            }
+            bytes.push(b);
+            self.bump();
+        }
+        Ok(bytes)
+    }
+
+    /// Consume bytes into `bytes` until it holds `len` bytes or the input ends
+    fn read_into(&mut self, bytes: &mut Vec<u8>, len: usize) -> Result<(), JsonError> {
+        while bytes.len() < len {
+            match self.next_byte()? {
+                Some(b) => bytes.push(b),
+                None => break,
+            }
+        }
+        Ok(())
+    }
+
+    /// The next byte of input, refilling the buffer once it has been read completely
+    fn peek(&mut self) -> Result<Option<u8>, JsonError> {
+        if self.pos == self.filled {
+            let filled = loop {
+                match self.reader.read(&mut self.buf) {
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => {
+                        let at = self.position;
+                        return Err(self.error(JsonErrorKind::Io(e.kind()), at));
+                    }
+                    Ok(filled) => break filled,
+                }
+            };
+            self.pos = 0;
+            self.filled = filled;
+        }
+
+        Ok(self.buf[..self.filled].get(self.pos).copied())
+    }
+
+    fn next_byte(&mut self) -> Result<Option<u8>, JsonError> {
+        let byte = self.peek()?;
+        if byte.is_some() {
+            self.bump();
+        }
+        Ok(byte)
+    }
+
+    /// Move past the byte returned by `peek`
+    fn bump(&mut self) {
+        let byte = self.buf[self.pos];
+        self.pos += 1;
+
+        self.position.offset += 1;
+        if byte == b'\n' {
+            self.position.line += 1;
+            self.position.column = 1;
+        } else if byte & 0xc0 != 0x80 {
+            // only bytes that start a UTF-8 character start a column
+            self.position.column += 1;
+        }
+    }
+
+    /// Error at `at`, with whatever input around it is still in the buffer as snippet
+    fn error(&self, kind: JsonErrorKind, at: Position) -> JsonError {
+        let back = self.position.offset - at.offset;
+        JsonError::in_window(
+            kind,
+            at.offset,
+            (at.line, at.column),
+            &self.buf[..self.filled],
+            self.pos.saturating_sub(back),
+        )
+    }
+}
+
+impl<R: Read> Iterator for JsonReader<R> {
+    type Item = Result<JsonEvent, JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        match self.next_event() {
+            Ok(event) => event.map(Ok),
+            Err(e) => {
+                self.failed = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
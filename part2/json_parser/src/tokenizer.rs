@@ -1,39 +1,133 @@
+use crate::error::{JsonError, JsonErrorKind};
+use crate::ParseOptions;
+use std::borrow::Cow;
+use std::ops::Range;
+
 #[derive(Debug)]
 pub enum JsonToken<'a> {
-    BeginArray,      // [
-    EndArray,        // ]
-    BeginObject,     // {
-    EndObject,       // }
-    String(&'a str), // "..."
-    Number(f64),     // 123.45
-    Boolean(bool),   // true | false
-    Null,            // null
-    Colon,           // :
-    Comma,           // ,
+    BeginArray,           // [
+    EndArray,             // ]
+    BeginObject,          // {
+    EndObject,            // }
+    String(Cow<'a, str>), // "..."
+    Number(f64),          // 123.45
+    Boolean(bool),        // true | false
+    Null,                 // null
+    Colon,                // :
+    Comma,                // ,
 }
 
 pub struct Tokenizer<'a> {
     pub input: &'a [u8],
+    /// The complete input, `input` is the part that has not been tokenized yet
+    source: &'a [u8],
+    options: ParseOptions,
 }
 
 impl<'a> Tokenizer<'a> {
-    pub fn new(input: &'a [u8]) -> Self {
-        Self { input }
+    pub fn new(input: &'a [u8], options: ParseOptions) -> Self {
+        Self {
+            input,
+            source: input,
+            options,
+        }
     }
-}
 
-impl<'a> Iterator for Tokenizer<'a> {
-    type Item = std::result::Result<JsonToken<'a>, String>;
+    /// Byte offset of the remaining input
+    fn offset(&self) -> usize {
+        self.source.len() - self.input.len()
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        // Skip whitespaces
-        while let Some(&byte) = self.input.first() {
-            if !byte.is_ascii_whitespace() {
-                break;
+    /// RFC 8259 only allows space, tab, line feed and carriage return between tokens
+    fn is_whitespace(&self, byte: u8) -> bool {
+        if self.options.strict {
+            matches!(byte, b' ' | b'\t' | b'\n' | b'\r')
+        } else {
+            byte.is_ascii_whitespace()
+        }
+    }
+
+    /// Scan a string starting at its opening quote. Strings without escapes borrow from the
+    /// input, only strings with escapes are decoded into an owned copy.
+    fn string(&mut self) -> Result<Cow<'a, str>, JsonError> {
+        let input = self.input;
+        let offset = self.offset();
+
+        // bytes before `start` have been copied into `decoded` already
+        let mut decoded: Option<Vec<u8>> = None;
+        let mut start = 1;
+        let mut end = 1;
+        loop {
+            match input.get(end) {
+                Some(b'"') => break,
+                Some(b'\\') => {
+                    let (ch, len) = unescape(&input[end..])
+                        .map_err(|kind| JsonError::new(kind, offset + end))?;
+                    let buf = decoded.get_or_insert_with(Vec::new);
+                    buf.extend_from_slice(&input[start..end]);
+                    buf.extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes());
+
+                    end += len;
+                    start = end;
+                }
+                Some(&b) if b < 0x20 && self.options.strict => {
+                    return Err(JsonError::new(
+                        JsonErrorKind::ControlCharacter,
+                        offset + end,
+                    ));
+                }
+                Some(_) => end += 1,
+                None => return Err(JsonError::new(JsonErrorKind::UnclosedString, offset)),
             }
-            self.input = &self.input[1..];
         }
 
+        // Escapes are ASCII, so validating the raw string also validates the decoded one
+        let raw = std::str::from_utf8(&input[1..end]).map_err(|e| {
+            JsonError::new(JsonErrorKind::InvalidUtf8, offset + 1 + e.valid_up_to())
+        })?;
+
+        // set the input to the bytes after the closing quote
+        self.input = &input[end + 1..];
+
+        match decoded {
+            None => Ok(Cow::Borrowed(raw)),
+            Some(mut buf) => {
+                buf.extend_from_slice(&input[start..end]);
+                let s = String::from_utf8(buf).expect("escapes split the string at ASCII bytes");
+                Ok(Cow::Owned(s))
+            }
+        }
+    }
+
+    /// Scan a number, loosely or following the RFC 8259 grammar in strict mode
+    fn number(&mut self) -> Result<f64, JsonError> {
+        let offset = self.offset();
+        let invalid = || JsonError::new(JsonErrorKind::InvalidNumber, offset);
+
+        let end = if self.options.strict {
+            strict_number_len(self.input).ok_or_else(invalid)?
+        } else {
+            self.input
+                .iter()
+                .position(|&b| {
+                    !b.is_ascii_digit() && b != b'.' && b != b'-' && b != b'e' && b != b'E'
+                })
+                .unwrap_or(self.input.len())
+        };
+
+        // only ASCII bytes were scanned
+        let num_str = std::str::from_utf8(&self.input[..end]).expect("number is ASCII");
+        let num = num_str.parse::<f64>().map_err(|_| invalid())?;
+        // e.g. 1e400 is valid grammar, but the infinity it rounds to is not a JSON number
+        if self.options.strict && !num.is_finite() {
+            return Err(invalid());
+        }
+        self.input = &self.input[end..];
+        Ok(num)
+    }
+
+    /// The token the remaining input starts with, whitespace has been skipped already
+    fn token(&mut self) -> Option<Result<JsonToken<'a>, JsonError>> {
         match self.input.first() {
             Some(b'{') => {
                 self.input = &self.input[1..];
@@ -59,19 +153,7 @@ impl<'a> Iterator for Tokenizer<'a> {
                 self.input = &self.input[1..];
                 Some(Ok(JsonToken::Comma))
             }
-            Some(b'"') => {
-                // continue iterator until we find the end of the string
-                if let Some(end) = self.input[1..].iter().position(|&b| b == b'"') {
-                    let s = std::str::from_utf8(&self.input[1..1 + end])
-                        .expect("Invalid UTF-8 sequence");
-
-                    // set the input to the remaining bytes
-                    self.input = &self.input[end + 2..];
-                    Some(Ok(JsonToken::String(s)))
-                } else {
-                    Some(Err("Unclosed string".to_string()))
-                }
-            }
+            Some(b'"') => Some(self.string().map(JsonToken::String)),
             Some(b't') if self.input.starts_with(b"true") => {
                 self.input = &self.input[4..];
                 Some(Ok(JsonToken::Boolean(true)))
@@ -86,26 +168,128 @@ impl<'a> Iterator for Tokenizer<'a> {
             }
             Some(&ch) if ch.is_ascii_digit() || ch == b'-' => {
                 // Capturing a number, this includes scientific notation e.g. 3.14e-2
-                let end = self
-                    .input
-                    .iter()
-                    .position(|&b| {
-                        !b.is_ascii_digit() && b != b'.' && b != b'-' && b != b'e' && b != b'E'
-                    })
-                    .unwrap_or(self.input.len());
-                let num_str = String::from_utf8(self.input[..end].to_vec())
-                    .map_err(|_| "Invalid UTF-8 sequence".to_string())
-                    .unwrap();
-                let num = num_str
-                    .parse::<f64>()
-                    .map_err(|_| "Invalid number format".to_string())
-                    .unwrap();
-                self.input = &self.input[end..];
-                Some(Ok(JsonToken::Number(num)))
+                Some(self.number().map(JsonToken::Number))
             }
             // string representation
-            Some(_) => Some(Err(format!("Unknown token: {}", self.input[0] as char))),
+            Some(_) => Some(Err(JsonError::new(
+                JsonErrorKind::UnknownCharacter,
+                self.offset(),
+            ))),
             None => None, // End of input
         }
     }
 }
+
+/// Length of the number at the start of `input` following the RFC 8259 grammar
+/// `-? (0 | [1-9][0-9]*) (.[0-9]+)? ([eE][+-]?[0-9]+)?`, or `None` if it is malformed
+pub(crate) fn strict_number_len(input: &[u8]) -> Option<usize> {
+    let digits = |from: usize| {
+        input[from..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count()
+    };
+
+    let mut len = usize::from(input.first() == Some(&b'-'));
+    match input.get(len) {
+        Some(b'0') => len += 1,
+        Some(b'1'..=b'9') => len += digits(len),
+        _ => return None,
+    }
+
+    if input.get(len) == Some(&b'.') {
+        let fraction = digits(len + 1);
+        if fraction == 0 {
+            return None;
+        }
+        len += 1 + fraction;
+    }
+
+    if let Some(b'e' | b'E') = input.get(len) {
+        len += 1;
+        if let Some(b'+' | b'-') = input.get(len) {
+            len += 1;
+        }
+        let exponent = digits(len);
+        if exponent == 0 {
+            return None;
+        }
+        len += exponent;
+    }
+
+    // more number characters directly after a valid number, e.g. a leading zero in 01
+    match input.get(len) {
+        Some(b) if b.is_ascii_digit() || matches!(b, b'.' | b'+' | b'-' | b'e' | b'E') => None,
+        _ => Some(len),
+    }
+}
+
+/// Decode the escape sequence at the start of `escape`, returning the character and the number of
+/// bytes the sequence takes up. Characters outside the Basic Multilingual Plane are written as a
+/// UTF-16 surrogate pair of two `\\u` escapes.
+pub(crate) fn unescape(escape: &[u8]) -> Result<(char, usize), JsonErrorKind> {
+    let ch = match escape.get(1) {
+        Some(b'"') => '"',
+        Some(b'\\') => '\\',
+        Some(b'/') => '/',
+        Some(b'b') => '\u{8}',
+        Some(b'f') => '\u{c}',
+        Some(b'n') => '\n',
+        Some(b'r') => '\r',
+        Some(b't') => '\t',
+        Some(b'u') => {
+            let high = hex_code_unit(escape.get(2..6))?;
+            if !(0xd800..0xdc00).contains(&high) {
+                // a low surrogate on its own is not a character either
+                let ch = char::from_u32(high).ok_or(JsonErrorKind::UnpairedSurrogate)?;
+                return Ok((ch, 6));
+            }
+
+            if escape.get(6..8) != Some(b"\\u") {
+                return Err(JsonErrorKind::UnpairedSurrogate);
+            }
+            let low = hex_code_unit(escape.get(8..12))?;
+            if !(0xdc00..0xe000).contains(&low) {
+                return Err(JsonErrorKind::UnpairedSurrogate);
+            }
+
+            let code_point = 0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00);
+            let ch = char::from_u32(code_point).expect("surrogate pairs are valid code points");
+            return Ok((ch, 12));
+        }
+        Some(_) => return Err(JsonErrorKind::InvalidEscape),
+        None => return Err(JsonErrorKind::UnclosedString),
+    };
+
+    Ok((ch, 2))
+}
+
+/// The four hex digits of a `\\u` escape
+fn hex_code_unit(digits: Option<&[u8]>) -> Result<u32, JsonErrorKind> {
+    match digits {
+        Some(digits) if digits.iter().all(u8::is_ascii_hexdigit) => {
+            let digits = std::str::from_utf8(digits).expect("hex digits are ASCII");
+            Ok(u32::from_str_radix(digits, 16).expect("four hex digits fit in a u32"))
+        }
+        _ => Err(JsonErrorKind::InvalidUnicodeEscape),
+    }
+}
+
+impl<'a> Iterator for Tokenizer<'a> {
+    /// A token with the byte range it spans in the input
+    type Item = Result<(JsonToken<'a>, Range<usize>), JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Skip whitespaces
+        while let Some(&byte) = self.input.first() {
+            if !self.is_whitespace(byte) {
+                break;
+            }
+            self.input = &self.input[1..];
+        }
+
+        let start = self.offset();
+        let token = self.token()?;
+        Some(token.map(|token| (token, start..self.offset())))
+    }
+}
@@ -1,6 +1,7 @@
 use haversine::haversine;
 use json_parser::{parse_json_str, parser::JsonValue};
 use simple_profiler::{ScopeGuard, PROFILE};
+use std::borrow::Cow;
 
 fn read_json_input(file_path: &str) -> String {
     let _ = ScopeGuard::start("read_json_input");
@@ -35,11 +36,16 @@ pub fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let args = std::env::args().collect::<Vec<String>>();
     assert_eq!(args.len(), 2, "Usage: <file_path>");
-    let json_input = read_json_input(&args[1]);
+    let file_path = &args[1];
+    let json_input = read_json_input(file_path);
 
     // parse the json
     let s = ScopeGuard::start("parse_json_str");
-    let result = parse_json_str(&json_input).expect("valid json");
+    // `?` would print the error's Debug form, show the message with its position instead
+    let result = parse_json_str(&json_input).unwrap_or_else(|e| {
+        eprintln!("{file_path}: {e}");
+        std::process::exit(1);
+    });
     std::mem::drop(s);
 
     // calculate the haversine sum
@@ -53,7 +59,7 @@ pub fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-fn get_number_from_kv(kv: &[(&str, JsonValue)], key: &str) -> f64 {
+fn get_number_from_kv(kv: &[(Cow<str>, JsonValue)], key: &str) -> f64 {
     if let Some((_, JsonValue::Number(val))) = kv.iter().find(|(k, _)| *k == key) {
         *val
     } else {
@@ -1,6 +1,7 @@
 use haversine::haversine;
 use json_parser::{parse_json_str, parser::JsonValue};
 use rdtsc::read_cpu_timer;
+use std::borrow::Cow;
 
 pub fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = std::env::args().collect::<Vec<String>>();
@@ -18,7 +19,11 @@ pub fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // parse the json
     let prof_parse = read_cpu_timer();
-    let result = parse_json_str(&json_input).expect("valid json");
+    // `?` would print the error's Debug form, show the message with its position instead
+    let result = parse_json_str(&json_input).unwrap_or_else(|e| {
+        eprintln!("{file_path}: {e}");
+        std::process::exit(1);
+    });
     let prof_sum = read_cpu_timer();
     // calculate the haversine distance sum
     let pairs = if let JsonValue::Object { kv } = &result {
@@ -65,7 +70,7 @@ pub fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-fn get_number_from_kv(kv: &[(&str, JsonValue)], key: &str) -> f64 {
+fn get_number_from_kv(kv: &[(Cow<str>, JsonValue)], key: &str) -> f64 {
     if let Some((_, JsonValue::Number(val))) = kv.iter().find(|(k, _)| *k == key) {
         *val
     } else {